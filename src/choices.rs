@@ -0,0 +1,78 @@
+use nzsc_core::{
+    characters::Character,
+    boosters::Booster,
+    moves::Move,
+};
+use std::fmt;
+
+/// A typed choice for whichever phase the game is in.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Choice {
+    Character(Character),
+    Booster(Booster),
+    Move(Move),
+}
+
+impl Choice {
+    pub fn character(self) -> Option<Character> {
+        if let Choice::Character(character) = self {
+            Some(character)
+        } else {
+            None
+        }
+    }
+
+    pub fn booster(self) -> Option<Booster> {
+        if let Choice::Booster(booster) = self {
+            Some(booster)
+        } else {
+            None
+        }
+    }
+
+    pub fn move_(self) -> Option<Move> {
+        if let Choice::Move(move_) = self {
+            Some(move_)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<Character> for Choice {
+    fn from(character: Character) -> Self {
+        Choice::Character(character)
+    }
+}
+
+impl From<Booster> for Choice {
+    fn from(booster: Booster) -> Self {
+        Choice::Booster(booster)
+    }
+}
+
+impl From<Move> for Choice {
+    fn from(move_: Move) -> Self {
+        Choice::Move(move_)
+    }
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Choice::Character(character) => write!(f, "{}", character),
+            Choice::Booster(booster) => write!(f, "{}", booster),
+            Choice::Move(move_) => write!(f, "{}", move_),
+        }
+    }
+}
+
+impl fmt::Debug for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Choice::Character(character) => write!(f, "Character({})", character),
+            Choice::Booster(booster) => write!(f, "Booster({})", booster),
+            Choice::Move(move_) => write!(f, "Move({:?})", move_),
+        }
+    }
+}
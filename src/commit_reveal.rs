@@ -0,0 +1,365 @@
+//! Simultaneous choices without a trusted referee.
+//!
+//! Each player first sends a `Commitment`: a salted hash of their choice.
+//! Once every player who has to choose has committed, each sends a `Reveal`
+//! holding the choice and salt, which must hash to their commitment. Since
+//! nobody reveals until both have committed, nobody can react to the other's
+//! choice, and since the commitment is fixed, nobody can change their own.
+//!
+//! A reveal that does not match its commitment forfeits the game, as does
+//! one that never arrives. A commitment that never arrives is penalized
+//! like any other timeout. Everything accepted is kept in a transcript,
+//! from which anyone can check an invalid reveal.
+//!
+//! If either revealed choice is penalized, only the penalties are applied.
+//! Any legal choice revealed alongside is thrown away and both players
+//! commit again. Otherwise a player could take a penalty on purpose, then
+//! choose again knowing their opponent's choice.
+
+use super::rules::RuleSet;
+use super::two_player_game::{
+    ChoiceError,
+    ChoiceOutcome,
+    NZSCTwoPlayerGame,
+    Phase,
+    WhichPlayer,
+    seat_index,
+};
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize, };
+use sha2::{ Digest, Sha256, };
+use std::error::Error;
+use std::fmt;
+
+/// Separates commitment hashes from any other use of SHA-256.
+const DOMAIN: &[u8] = b"nzsc2p commitment";
+
+/// A salted SHA-256 hash of a choice.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Commitment(pub [u8; 32]);
+
+/// A committed choice, opened.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Reveal {
+    /// The choice, as it would be passed to `process_choice`.
+    pub choice: String,
+    /// Random bytes that keep the commitment from being guessed.
+    ///
+    /// Use a fresh, unpredictable salt for every choice, such as one read
+    /// from the operating system's random number generator.
+    pub salt: [u8; 32],
+}
+
+/// A message accepted by a `CommitRevealGame`.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Message {
+    Commit(Commitment),
+    Reveal(Reveal),
+    /// The player did not commit or reveal in time.
+    TimeOut,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TranscriptEntry {
+    pub player: WhichPlayer,
+    pub message: Message,
+}
+
+/// Proof that a player revealed something other than what they committed to.
+///
+/// Anyone holding the transcript can check it with `is_valid`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct InvalidReveal {
+    pub player: WhichPlayer,
+    /// Where in the transcript the commitment and the reveal are.
+    pub commit_index: usize,
+    pub reveal_index: usize,
+    pub commitment: Commitment,
+    pub reveal: Reveal,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CommitRevealError {
+    /// The game rejected the player's turn, for example because the game is over.
+    Choice(ChoiceError),
+    AlreadyCommitted,
+    /// The player has nothing to reveal.
+    NotCommitted,
+    AlreadyRevealed,
+    /// Reveals are only accepted once everyone who has to choose has committed.
+    WaitingForCommitments,
+    /// The player is not waiting to commit or reveal, so cannot time out.
+    NotPending,
+}
+
+/// A game played by commit and reveal instead of by handing choices to a referee.
+#[derive(Clone)]
+pub struct CommitRevealGame {
+    game: NZSCTwoPlayerGame,
+    commitments: [Option<Commitment>; 2],
+    reveals: [Option<Reveal>; 2],
+    transcript: Vec<TranscriptEntry>,
+}
+
+/// Commits to `choice` with `salt`.
+pub fn commit(choice: &str, salt: [u8; 32]) -> Commitment {
+    let mut hasher = Sha256::new();
+    hasher.update(DOMAIN);
+    hasher.update(salt);
+    hasher.update(choice.as_bytes());
+
+    Commitment(hasher.finalize().into())
+}
+
+impl Reveal {
+    pub fn new(choice: String, salt: [u8; 32]) -> Self {
+        Self {
+            choice,
+            salt,
+        }
+    }
+
+    /// The commitment this reveal opens.
+    pub fn commitment(&self) -> Commitment {
+        commit(&self.choice, self.salt)
+    }
+}
+
+impl InvalidReveal {
+    /// Whether this really proves a mismatch.
+    pub fn is_valid(&self) -> bool {
+        self.reveal.commitment() != self.commitment
+    }
+
+    /// Every invalid reveal in `transcript`.
+    pub fn find_all(transcript: &[TranscriptEntry]) -> Vec<Self> {
+        let mut commitments: [Option<(usize, Commitment)>; 2] = [None, None];
+        let mut invalid = vec![];
+
+        for (index, entry) in transcript.iter().enumerate() {
+            let slot = &mut commitments[seat_index(entry.player)];
+
+            match entry.message {
+                Message::Commit(commitment) => *slot = Some((index, commitment)),
+                Message::Reveal(ref reveal) => {
+                    if let Some((commit_index, commitment)) = slot.take() {
+                        if reveal.commitment() != commitment {
+                            invalid.push(Self {
+                                player: entry.player,
+                                commit_index,
+                                reveal_index: index,
+                                commitment,
+                                reveal: reveal.clone(),
+                            });
+                        }
+                    }
+                },
+                Message::TimeOut => *slot = None,
+            }
+        }
+
+        invalid
+    }
+}
+
+impl CommitRevealGame {
+    pub fn new() -> Self {
+        Self::with_rules(RuleSet::default())
+    }
+
+    pub fn with_rules(rules: RuleSet) -> Self {
+        Self {
+            game: NZSCTwoPlayerGame::with_rules(rules),
+            commitments: [None, None],
+            reveals: [None, None],
+            transcript: vec![],
+        }
+    }
+
+    /// Rebuilds a game from its transcript.
+    pub fn replay(rules: RuleSet, transcript: &[TranscriptEntry]) -> Result<Self, (usize, CommitRevealError)> {
+        let mut game = Self::with_rules(rules);
+
+        for (index, entry) in transcript.iter().enumerate() {
+            let result = match entry.message {
+                Message::Commit(commitment) => game.commit(entry.player, commitment).map(|_| vec![]),
+                Message::Reveal(ref reveal) => game.reveal(entry.player, reveal.clone()),
+                Message::TimeOut => game.time_out(entry.player).map(|outcome| vec![(entry.player, outcome)]),
+            };
+
+            result.map_err(|error| (index, error))?;
+        }
+
+        Ok(game)
+    }
+
+    /// The game as resolved so far. Choices are only applied once both are revealed.
+    pub fn game(&self) -> &NZSCTwoPlayerGame {
+        &self.game
+    }
+
+    /// Every accepted message, oldest first.
+    pub fn transcript(&self) -> &[TranscriptEntry] {
+        &self.transcript
+    }
+
+    pub fn commitment(&self, player: WhichPlayer) -> Option<Commitment> {
+        self.commitments[seat_index(player)]
+    }
+
+    /// Whether the game is waiting on `player` to commit or reveal.
+    pub fn is_pending(&self, player: WhichPlayer) -> bool {
+        let index = seat_index(player);
+
+        self.game.is_waiting_on(player) && match self.commitments[index] {
+            None => true,
+            Some(_) => self.everyone_committed() && self.reveals[index].is_none(),
+        }
+    }
+
+    pub fn commit(&mut self, player: WhichPlayer, commitment: Commitment) -> Result<(), CommitRevealError> {
+        if let Phase::GameOver(_, _) = self.game.phase {
+            return Err(CommitRevealError::Choice(ChoiceError::GameOver));
+        }
+        if !self.game.is_waiting_on(player) {
+            return Err(CommitRevealError::Choice(ChoiceError::AlreadyChosen));
+        }
+        if self.commitments[seat_index(player)].is_some() {
+            return Err(CommitRevealError::AlreadyCommitted);
+        }
+
+        self.commitments[seat_index(player)] = Some(commitment);
+        self.transcript.push(TranscriptEntry {
+            player,
+            message: Message::Commit(commitment),
+        });
+
+        Ok(())
+    }
+
+    /// Opens `player`'s commitment.
+    ///
+    /// Returns every choice applied as a result: none until all commitments
+    /// are revealed, only the penalized ones if any are, or a forfeit if
+    /// `reveal` does not match.
+    pub fn reveal(&mut self, player: WhichPlayer, reveal: Reveal) -> Result<Vec<(WhichPlayer, ChoiceOutcome)>, CommitRevealError> {
+        let index = seat_index(player);
+        let commitment = self.commitments[index].ok_or(CommitRevealError::NotCommitted)?;
+
+        if self.reveals[index].is_some() {
+            return Err(CommitRevealError::AlreadyRevealed);
+        }
+        if !self.everyone_committed() {
+            return Err(CommitRevealError::WaitingForCommitments);
+        }
+
+        let matches = reveal.commitment() == commitment;
+        self.transcript.push(TranscriptEntry {
+            player,
+            message: Message::Reveal(reveal.clone()),
+        });
+
+        if !matches {
+            self.commitments = [None, None];
+            self.reveals = [None, None];
+            let outcome = self.game.forfeit(player).map_err(CommitRevealError::Choice)?;
+
+            return Ok(vec![(player, outcome)]);
+        }
+
+        self.reveals[index] = Some(reveal);
+
+        Ok(self.apply_reveals())
+    }
+
+    /// Handles `player` failing to commit or reveal in time.
+    ///
+    /// A missing commitment is penalized like `NZSCTwoPlayerGame::time_out`,
+    /// and the player must then commit. A missing reveal forfeits the game,
+    /// since the opponent may already have revealed their choice.
+    pub fn time_out(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, CommitRevealError> {
+        if !self.is_pending(player) {
+            return Err(CommitRevealError::NotPending);
+        }
+
+        let outcome = if self.commitments[seat_index(player)].is_some() {
+            self.game.forfeit(player)
+        } else {
+            self.game.time_out(player)
+        }.map_err(CommitRevealError::Choice)?;
+        self.transcript.push(TranscriptEntry {
+            player,
+            message: Message::TimeOut,
+        });
+
+        if let Phase::GameOver(_, _) = self.game.phase {
+            self.commitments = [None, None];
+            self.reveals = [None, None];
+        }
+
+        Ok(outcome)
+    }
+
+    /// Whether everyone who has to choose has committed, so reveals are open.
+    fn everyone_committed(&self) -> bool {
+        [WhichPlayer::PlayerA, WhichPlayer::PlayerB].iter()
+            .all(|&player| !self.game.is_waiting_on(player) || self.commitments[seat_index(player)].is_some())
+    }
+
+    /// Applies the revealed choices once every commitment has been opened.
+    ///
+    /// If any choice is penalized, the legal ones are dropped and everyone commits again.
+    fn apply_reveals(&mut self) -> Vec<(WhichPlayer, ChoiceOutcome)> {
+        let all_revealed = self.commitments.iter().zip(&self.reveals)
+            .all(|(commitment, reveal)| commitment.is_none() || reveal.is_some());
+
+        if !all_revealed || !self.everyone_committed() {
+            return vec![];
+        }
+
+        let choices: Vec<(WhichPlayer, String, bool)> = [WhichPlayer::PlayerA, WhichPlayer::PlayerB].iter()
+            .filter_map(|&player| {
+                let reveal = self.reveals[seat_index(player)].take()?;
+                let is_penalized = matches!(self.game.evaluate_choice(player, &reveal.choice), Ok(Some(_)));
+
+                Some((player, reveal.choice, is_penalized))
+            })
+            .collect();
+        let any_penalized = choices.iter().any(|&(_, _, is_penalized)| is_penalized);
+        self.commitments = [None, None];
+
+        let applied = choices.into_iter()
+            .filter(|&(_, _, is_penalized)| is_penalized || !any_penalized)
+            .map(|(player, choice, _)| (player, choice));
+
+        // Both players were waiting when they committed, so neither choice is rejected.
+        self.game.process_simultaneous_choices(applied).into_iter()
+            .filter_map(|(player, result)| result.ok().map(|outcome| (player, outcome)))
+            .collect()
+    }
+}
+
+impl Default for CommitRevealGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for CommitRevealError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CommitRevealError::Choice(ref error) => write!(f, "{}", error),
+            CommitRevealError::AlreadyCommitted => write!(f, "Player has already committed"),
+            CommitRevealError::NotCommitted => write!(f, "Player has not committed"),
+            CommitRevealError::AlreadyRevealed => write!(f, "Player has already revealed"),
+            CommitRevealError::WaitingForCommitments => write!(f, "Not every player has committed"),
+            CommitRevealError::NotPending => write!(f, "Player is not waiting to commit or reveal"),
+        }
+    }
+}
+
+impl Error for CommitRevealError {}
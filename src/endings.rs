@@ -0,0 +1,120 @@
+//! Ending a game before anyone reaches the winning score.
+
+use super::events::Event;
+use super::history::Action;
+use super::two_player_game::{
+    ChoiceError,
+    ChoiceOutcome,
+    NZSCTwoPlayerGame,
+    Phase,
+    WhichPlayer,
+};
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize, };
+
+/// How a game ended.
+///
+/// The score in `Phase::GameOver` is always the score when the game ended,
+/// so it only decides the winner for `GameEnd::Points`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GameEnd {
+    /// A player reached `RuleSet::points_to_win`.
+    Points,
+    /// This player resigned.
+    Resigned(WhichPlayer),
+    /// This player was forfeited.
+    Forfeited(WhichPlayer),
+    /// The players agreed to a draw.
+    Drawn,
+}
+
+impl NZSCTwoPlayerGame {
+    /// How the game ended, or `None` if it is still going.
+    pub fn end(&self) -> Option<GameEnd> {
+        self.end
+    }
+
+    /// Who won, or `None` if the game is still going or was drawn.
+    pub fn winner(&self) -> Option<WhichPlayer> {
+        match self.end? {
+            GameEnd::Points => match self.phase {
+                Phase::GameOver(a_points, b_points) => Some(if a_points > b_points {
+                    WhichPlayer::PlayerA
+                } else {
+                    WhichPlayer::PlayerB
+                }),
+                _ => None,
+            },
+            GameEnd::Resigned(loser) | GameEnd::Forfeited(loser) => Some(loser.opponent()),
+            GameEnd::Drawn => None,
+        }
+    }
+
+    /// The player whose draw offer is waiting for an answer.
+    pub fn draw_offer(&self) -> Option<WhichPlayer> {
+        self.draw_offer
+    }
+
+    /// Gives up the game, so the opponent wins.
+    pub fn resign(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        self.end_early(player, Action::Resign, GameEnd::Resigned(player), Event::Resigned { player })
+    }
+
+    /// Ends the game against `player`, for an administrator or a turn timer.
+    pub fn forfeit(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        self.end_early(player, Action::Forfeit, GameEnd::Forfeited(player), Event::Forfeited { player })
+    }
+
+    /// Offers the opponent a draw.
+    ///
+    /// The offer stands until the opponent accepts it or makes a choice.
+    /// Offering when the opponent already has is the same as accepting.
+    pub fn offer_draw(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        if self.draw_offer == Some(player.opponent()) {
+            return self.accept_draw(player);
+        }
+        if let Phase::GameOver(_, _) = self.phase {
+            return Err(ChoiceError::GameOver);
+        }
+
+        self.draw_offer = Some(player);
+        let events = vec![Event::DrawOffered { player }];
+        self.record(player, Action::OfferDraw, &events);
+
+        Ok(ChoiceOutcome {
+            penalty: None,
+            events,
+        })
+    }
+
+    /// Accepts the opponent's draw offer, ending the game.
+    pub fn accept_draw(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        if self.draw_offer != Some(player.opponent()) {
+            return Err(ChoiceError::NoDrawOffer);
+        }
+
+        self.end_early(player, Action::AcceptDraw, GameEnd::Drawn, Event::DrawAccepted { player })
+    }
+
+    fn end_early(&mut self, player: WhichPlayer, action: Action, end: GameEnd, event: Event) -> Result<ChoiceOutcome, ChoiceError> {
+        let (a_points, b_points) = match self.phase {
+            Phase::CharacterChoosing(ref a, ref b) => (a.points, b.points),
+            Phase::BoosterChoosing(ref a, ref b) => (a.points, b.points),
+            Phase::MoveChoosing(ref a, ref b) => (a.points, b.points),
+            Phase::GameOver(_, _) => return Err(ChoiceError::GameOver),
+        };
+
+        self.phase = Phase::GameOver(a_points, b_points);
+        self.end = Some(end);
+        self.draw_offer = None;
+
+        let events = vec![event, Event::GameOver { a_points, b_points }];
+        self.record(player, action, &events);
+
+        Ok(ChoiceOutcome {
+            penalty: None,
+            events,
+        })
+    }
+}
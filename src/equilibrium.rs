@@ -0,0 +1,187 @@
+//! Mixed-strategy equilibria for the move phase.
+//!
+//! A round is treated as a zero-sum game where each player's payoff is
+//! their points minus their opponent's points, so the equilibrium is the
+//! pair of move probabilities neither player can exploit.
+
+use super::rules::RuleSet;
+use super::strategies::move_score;
+use super::two_player_game::Phase;
+use nzsc_core::moves::Move;
+
+/// How many points player A gains over player B for every pair of moves.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PayoffMatrix {
+    pub a_moves: Vec<Move>,
+    pub b_moves: Vec<Move>,
+    /// `payoffs[i][j]` is A's points minus B's when A plays `a_moves[i]` and B plays `b_moves[j]`.
+    pub payoffs: Vec<Vec<i32>>,
+}
+
+/// The solution of a zero-sum matrix game.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MatrixSolution {
+    /// The probability the row player picks each row.
+    pub row_strategy: Vec<f64>,
+    /// The probability the column player picks each column.
+    pub column_strategy: Vec<f64>,
+    /// The row player's expected payoff when both play their strategies.
+    pub value: f64,
+}
+
+/// Move probabilities for both players of a move phase.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Equilibrium {
+    pub a_strategy: Vec<(Move, f64)>,
+    pub b_strategy: Vec<(Move, f64)>,
+    /// How many points per round A expects to gain over B.
+    pub value: f64,
+}
+
+const EPSILON: f64 = 1e-9;
+
+impl PayoffMatrix {
+    /// Builds the payoff matrix for a `Phase::MoveChoosing`, or `None` for any
+    /// other phase or when either player has no available moves.
+    pub fn for_phase(phase: &Phase, rules: &RuleSet) -> Option<Self> {
+        if let Phase::MoveChoosing(ref a, ref b) = *phase {
+            let a_moves = a.available_moves(rules);
+            let b_moves = b.available_moves(rules);
+            if a_moves.is_empty() || b_moves.is_empty() {
+                return None;
+            }
+            let payoffs = a_moves.iter()
+                .map(|&a_move| {
+                    b_moves.iter()
+                        .map(|&b_move| move_score(a.booster, a_move, b.booster, b_move))
+                        .collect()
+                })
+                .collect();
+
+            Some(Self {
+                a_moves,
+                b_moves,
+                payoffs,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Solves the matrix, or returns `None` if either player has no moves.
+    pub fn solve(&self) -> Option<Equilibrium> {
+        let payoffs: Vec<Vec<f64>> = self.payoffs.iter()
+            .map(|row| row.iter().map(|&payoff| payoff as f64).collect())
+            .collect();
+        let solution = solve_matrix_game(&payoffs)?;
+
+        Some(Equilibrium {
+            a_strategy: self.a_moves.iter().cloned().zip(solution.row_strategy).collect(),
+            b_strategy: self.b_moves.iter().cloned().zip(solution.column_strategy).collect(),
+            value: solution.value,
+        })
+    }
+}
+
+/// Solves the move phase of `phase`, or returns `None` if it is not in the
+/// move phase or either player has no available moves.
+pub fn move_equilibrium(phase: &Phase, rules: &RuleSet) -> Option<Equilibrium> {
+    PayoffMatrix::for_phase(phase, rules).and_then(|matrix| matrix.solve())
+}
+
+/// Solves a zero-sum game given the row player's payoffs, using the simplex method.
+///
+/// Returns `None` if there are no rows or no columns. Rows must all be the same length.
+pub fn solve_matrix_game(payoffs: &[Vec<f64>]) -> Option<MatrixSolution> {
+    let rows = payoffs.len();
+    let columns = payoffs.first().map_or(0, |row| row.len());
+    if rows == 0 || columns == 0 {
+        return None;
+    }
+
+    // Shift every payoff to be positive so the game's value is positive.
+    let min = payoffs.iter().flat_map(|row| row.iter().cloned()).fold(f64::INFINITY, f64::min);
+    let shift = 1.0 - min;
+
+    // The column player's program: maximize sum(y) subject to payoffs * y <= 1, y >= 0.
+    // The tableau holds one row per constraint plus the objective, with a slack
+    // column per constraint and the right-hand side last.
+    let width = columns + rows + 1;
+    let mut tableau = vec![vec![0.0; width]; rows + 1];
+    for i in 0..rows {
+        for j in 0..columns {
+            tableau[i][j] = payoffs[i][j] + shift;
+        }
+        tableau[i][columns + i] = 1.0;
+        tableau[i][width - 1] = 1.0;
+    }
+    for value in tableau[rows].iter_mut().take(columns) {
+        *value = -1.0;
+    }
+
+    let mut basis: Vec<usize> = (columns..columns + rows).collect();
+
+    // Bland's rule: the lowest-indexed improving column, so the method cannot cycle.
+    while let Some(entering) = (0..width - 1).find(|&j| tableau[rows][j] < -EPSILON) {
+        let mut leaving: Option<usize> = None;
+        for i in 0..rows {
+            if tableau[i][entering] > EPSILON {
+                let ratio = tableau[i][width - 1] / tableau[i][entering];
+                let is_better = match leaving {
+                    None => true,
+                    Some(l) => {
+                        let best = tableau[l][width - 1] / tableau[l][entering];
+                        ratio < best - EPSILON || (ratio < best + EPSILON && basis[i] < basis[l])
+                    },
+                };
+
+                if is_better {
+                    leaving = Some(i);
+                }
+            }
+        }
+
+        // Every shifted payoff is positive, so the program is bounded and a row always leaves.
+        let leaving = leaving.expect("bounded linear program");
+        pivot(&mut tableau, leaving, entering);
+        basis[leaving] = entering;
+    }
+
+    let total = tableau[rows][width - 1];
+    let shifted_value = 1.0 / total;
+
+    let mut column_strategy = vec![0.0; columns];
+    for (i, &variable) in basis.iter().enumerate() {
+        if variable < columns {
+            column_strategy[variable] = tableau[i][width - 1] * shifted_value;
+        }
+    }
+    let row_strategy = (0..rows)
+        .map(|i| tableau[rows][columns + i] * shifted_value)
+        .collect();
+
+    Some(MatrixSolution {
+        row_strategy,
+        column_strategy,
+        value: shifted_value - shift,
+    })
+}
+
+fn pivot(tableau: &mut [Vec<f64>], row: usize, column: usize) {
+    let divisor = tableau[row][column];
+    for value in tableau[row].iter_mut() {
+        *value /= divisor;
+    }
+
+    let pivot_row = tableau[row].clone();
+    for (i, other) in tableau.iter_mut().enumerate() {
+        if i != row {
+            let factor = other[column];
+            if factor != 0.0 {
+                for (value, &pivot_value) in other.iter_mut().zip(&pivot_row) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+    }
+}
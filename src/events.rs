@@ -0,0 +1,159 @@
+use super::two_player_game::{
+    WhichPlayer,
+    Penalty,
+};
+use nzsc_core::{
+    characters::Character,
+    boosters::Booster,
+    moves::Move,
+};
+use std::fmt;
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize, };
+
+/// Something that happened while processing a choice.
+///
+/// Events are listed in the order they happened.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Event {
+    /// A player locked in a character.
+    CharacterSelected {
+        player: WhichPlayer,
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::CharacterDef"))]
+        character: Character,
+    },
+    /// Both players chose the same character and must choose again.
+    CharactersTied(
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::CharacterDef"))]
+        Character
+    ),
+    /// A player earned points from their character beating the opponent's.
+    HeadstartAwarded {
+        player: WhichPlayer,
+        points: u8,
+    },
+    /// A player locked in a booster.
+    BoosterSelected {
+        player: WhichPlayer,
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::BoosterDef"))]
+        booster: Booster,
+    },
+    /// A player locked in a move.
+    MoveSelected {
+        player: WhichPlayer,
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::MoveDef"))]
+        selected_move: Move,
+    },
+    /// Both players' moves were revealed and scored.
+    MovesRevealed {
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::MoveDef"))]
+        a_move: Move,
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::MoveDef"))]
+        b_move: Move,
+        a_points: u8,
+        b_points: u8,
+    },
+    /// A player's move was destroyed by the opponent's destructive move.
+    MoveDestroyed {
+        player: WhichPlayer,
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::MoveDef"))]
+        destroyed_move: Move,
+    },
+    /// A player used up a single-use move.
+    SingleUseConsumed {
+        player: WhichPlayer,
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::MoveDef"))]
+        consumed_move: Move,
+    },
+    /// A player made a penalized choice.
+    PenaltyApplied {
+        player: WhichPlayer,
+        penalty: Penalty,
+    },
+    /// A player gave up the game.
+    Resigned {
+        player: WhichPlayer,
+    },
+    /// A player was made to give up the game, for example by running out of time.
+    Forfeited {
+        player: WhichPlayer,
+    },
+    /// A player offered to end the game in a draw.
+    DrawOffered {
+        player: WhichPlayer,
+    },
+    /// A player accepted their opponent's draw offer.
+    DrawAccepted {
+        player: WhichPlayer,
+    },
+    /// Both players reached the winning score together, so both were set back to this score.
+    SuddenDeathReset {
+        points: u8,
+    },
+    /// The game ended with this score.
+    GameOver {
+        a_points: u8,
+        b_points: u8,
+    },
+}
+
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Event::CharacterSelected { player, character } => debug_struct(f, "CharacterSelected", &[
+                ("player", &player), ("character", &DisplayAsDebug(character)),
+            ]),
+            Event::CharactersTied(character) => f.debug_tuple("CharactersTied").field(&DisplayAsDebug(character)).finish(),
+            Event::HeadstartAwarded { player, points } => debug_struct(f, "HeadstartAwarded", &[
+                ("player", &player), ("points", &points),
+            ]),
+            Event::BoosterSelected { player, booster } => debug_struct(f, "BoosterSelected", &[
+                ("player", &player), ("booster", &DisplayAsDebug(booster)),
+            ]),
+            Event::MoveSelected { player, selected_move } => debug_struct(f, "MoveSelected", &[
+                ("player", &player), ("selected_move", &selected_move),
+            ]),
+            Event::MovesRevealed { a_move, b_move, a_points, b_points } => debug_struct(f, "MovesRevealed", &[
+                ("a_move", &a_move), ("b_move", &b_move), ("a_points", &a_points), ("b_points", &b_points),
+            ]),
+            Event::MoveDestroyed { player, destroyed_move } => debug_struct(f, "MoveDestroyed", &[
+                ("player", &player), ("destroyed_move", &destroyed_move),
+            ]),
+            Event::SingleUseConsumed { player, consumed_move } => debug_struct(f, "SingleUseConsumed", &[
+                ("player", &player), ("consumed_move", &consumed_move),
+            ]),
+            Event::PenaltyApplied { player, penalty } => debug_struct(f, "PenaltyApplied", &[
+                ("player", &player), ("penalty", &penalty),
+            ]),
+            Event::Resigned { player } => debug_struct(f, "Resigned", &[("player", &player)]),
+            Event::Forfeited { player } => debug_struct(f, "Forfeited", &[("player", &player)]),
+            Event::DrawOffered { player } => debug_struct(f, "DrawOffered", &[("player", &player)]),
+            Event::DrawAccepted { player } => debug_struct(f, "DrawAccepted", &[("player", &player)]),
+            Event::SuddenDeathReset { points } => debug_struct(f, "SuddenDeathReset", &[("points", &points)]),
+            Event::GameOver { a_points, b_points } => debug_struct(f, "GameOver", &[
+                ("a_points", &a_points), ("b_points", &b_points),
+            ]),
+        }
+    }
+}
+
+/// Shows a value's `Display` output where `Debug` is expected.
+///
+/// `Character` and `Booster` only implement `Display`.
+pub(crate) struct DisplayAsDebug<T>(pub T);
+
+impl<T: fmt::Display> fmt::Debug for DisplayAsDebug<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// Writes `name { field: value, .. }` like `#[derive(Debug)]` would.
+pub(crate) fn debug_struct(f: &mut fmt::Formatter, name: &str, fields: &[(&str, &dyn fmt::Debug)]) -> fmt::Result {
+    let mut builder = f.debug_struct(name);
+    for &(field, value) in fields {
+        builder.field(field, value);
+    }
+    builder.finish()
+}
@@ -0,0 +1,147 @@
+use super::events::Event;
+use super::rules::RuleSet;
+use super::two_player_game::{
+    NZSCTwoPlayerGame,
+    WhichPlayer,
+    ChoiceError,
+    ChoiceOutcome,
+};
+use nzsc_core::characters::Character;
+use std::fmt;
+use std::error::Error;
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize, };
+
+/// One accepted action and what it did.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HistoryEntry {
+    pub player: WhichPlayer,
+    pub action: Action,
+    pub events: Vec<Event>,
+}
+
+/// Something a player did, or had done to them, that changed the game.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Action {
+    /// A choice exactly as it was submitted.
+    Choose(String),
+    /// The player's turn timer ran out. See `NZSCTwoPlayerGame::time_out`.
+    TimeOut,
+    /// See `NZSCTwoPlayerGame::resign`.
+    Resign,
+    /// See `NZSCTwoPlayerGame::forfeit`.
+    Forfeit,
+    /// See `NZSCTwoPlayerGame::offer_draw`.
+    OfferDraw,
+    /// See `NZSCTwoPlayerGame::accept_draw`.
+    AcceptDraw,
+}
+
+/// Why a history could not be replayed.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ReplayError {
+    /// The entry at `index` was rejected by the game.
+    Rejected {
+        index: usize,
+        error: ChoiceError,
+    },
+    /// The entry at `index` produced different events than the ones recorded.
+    Diverged {
+        index: usize,
+    },
+}
+
+impl NZSCTwoPlayerGame {
+    /// Rebuilds a game by replaying `history` from the start.
+    ///
+    /// Every entry's recorded events are checked against the replayed ones,
+    /// so a tampered history is reported rather than silently accepted.
+    pub fn replay(history: &[HistoryEntry]) -> Result<Self, ReplayError> {
+        Self::replay_with_rules(RuleSet::default(), history)
+    }
+
+    /// Like `replay`, for a game played with `rules`.
+    pub fn replay_with_rules(rules: RuleSet, history: &[HistoryEntry]) -> Result<Self, ReplayError> {
+        Self::replay_with_bans(rules, [vec![], vec![]], history)
+    }
+
+    /// Like `replay`, for a game started with `NZSCTwoPlayerGame::with_bans`.
+    pub fn replay_with_bans(rules: RuleSet, banned_characters: [Vec<Character>; 2], history: &[HistoryEntry]) -> Result<Self, ReplayError> {
+        let mut game = NZSCTwoPlayerGame::with_bans(rules, banned_characters);
+        game.replay_onto(history)?;
+
+        Ok(game)
+    }
+
+    /// Applies every entry of `history` in turn, checking its recorded events.
+    fn replay_onto(&mut self, history: &[HistoryEntry]) -> Result<(), ReplayError> {
+        for (index, entry) in history.iter().enumerate() {
+            let outcome = self.apply(entry.player, entry.action.clone())
+                .map_err(|error| ReplayError::Rejected { index, error })?;
+
+            if outcome.events != entry.events {
+                return Err(ReplayError::Diverged { index });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Performs `action` for `player`, recording it in the history.
+    pub fn apply(&mut self, player: WhichPlayer, action: Action) -> Result<ChoiceOutcome, ChoiceError> {
+        match action {
+            Action::Choose(choice) => self.process_choice(player, choice),
+            Action::TimeOut => self.time_out(player),
+            Action::Resign => self.resign(player),
+            Action::Forfeit => self.forfeit(player),
+            Action::OfferDraw => self.offer_draw(player),
+            Action::AcceptDraw => self.accept_draw(player),
+        }
+    }
+
+    /// Takes back the most recent accepted action, returning it.
+    ///
+    /// See `rewind_to` for when this fails.
+    pub fn undo(&mut self) -> Result<Option<HistoryEntry>, ReplayError> {
+        let turn_index = match self.history.len().checked_sub(1) {
+            Some(turn_index) => turn_index,
+            None => return Ok(None),
+        };
+
+        Ok(self.rewind_to(turn_index)?.pop())
+    }
+
+    /// Restores the game to how it was just before the action at `turn_index`
+    /// in `history()`, returning that action and every later one.
+    ///
+    /// The earlier actions are replayed from the phase the game started in.
+    /// Does nothing if `turn_index` is not before the end of the history, and
+    /// leaves the game unchanged if the earlier actions no longer replay, as
+    /// can happen with a history edited after deserializing.
+    pub fn rewind_to(&mut self, turn_index: usize) -> Result<Vec<HistoryEntry>, ReplayError> {
+        if turn_index >= self.history.len() {
+            return Ok(vec![]);
+        }
+
+        let mut game = NZSCTwoPlayerGame::from_phase(self.start.clone(), *self.rules());
+        game.replay_onto(&self.history[..turn_index])?;
+
+        let undone = self.history.split_off(turn_index);
+        *self = game;
+
+        Ok(undone)
+    }
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReplayError::Rejected { index, error } => write!(f, "History entry {} was rejected: {}", index, error),
+            ReplayError::Diverged { index } => write!(f, "History entry {} does not match its recorded events", index),
+        }
+    }
+}
+
+impl Error for ReplayError {}
@@ -1,8 +1,44 @@
+#![allow(clippy::needless_return)]
+
 extern crate nzsc_core;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "server")]
+extern crate serde_json;
+#[cfg(feature = "commit-reveal")]
+extern crate sha2;
+#[cfg(test)]
+#[macro_use]
+extern crate proptest;
 
 mod tests;
+mod seats;
+#[cfg(feature = "serde")]
+mod serialization;
 
+pub mod choices;
+#[cfg(feature = "commit-reveal")]
+pub mod commit_reveal;
+pub mod endings;
+pub mod equilibrium;
+pub mod events;
+pub mod history;
+pub mod lobby;
+pub mod lockstep;
+pub mod mcts;
+pub mod multiplayer;
 pub mod players;
+pub mod random;
+pub mod rules;
+pub mod series;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod simulation;
+pub mod state_hash;
+pub mod strategies;
+pub mod teams;
+pub mod timers;
 pub mod two_player_game;
+pub mod views;
 
 pub use nzsc_core::*;
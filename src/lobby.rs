@@ -0,0 +1,375 @@
+//! Rooms for networked play: joining by code, random matchmaking,
+//! spectating, rematches and cleanup.
+//!
+//! The lobby knows nothing about connections. Clients are identified by
+//! a `ClientId` the caller assigns, and the caller passes in the current
+//! time, so the same lobby can sit behind any transport.
+
+use super::random::Rng;
+use super::rules::RuleSet;
+use super::two_player_game::{
+    ChoiceError,
+    ChoiceOutcome,
+    NZSCTwoPlayerGame,
+    Phase,
+    WhichPlayer,
+    seat_index,
+};
+use std::collections::{ HashMap, VecDeque, };
+use std::error::Error;
+use std::fmt;
+use std::time::{ Duration, Instant, };
+
+pub type ClientId = u64;
+
+/// Letters used in room codes, leaving out ones that are easy to misread.
+const CODE_LETTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+const CODE_LENGTH: usize = 5;
+
+pub struct Lobby {
+    rng: Rng,
+    rooms: HashMap<String, Room>,
+    members: HashMap<ClientId, Membership>,
+    queue: VecDeque<ClientId>,
+    /// The rules for every new game.
+    pub rules: RuleSet,
+    /// Rooms with no activity for this long are closed by `remove_idle_rooms`.
+    pub idle_timeout: Duration,
+}
+
+/// A game and everyone in it.
+pub struct Room {
+    pub code: String,
+    pub game: NZSCTwoPlayerGame,
+    /// Who is seated as player A, then player B. A seat is empty until someone joins or after they leave.
+    pub players: [Option<ClientId>; 2],
+    pub spectators: Vec<ClientId>,
+    /// Which players have asked for a rematch since the game ended.
+    pub rematch_requested: [bool; 2],
+    last_activity: Instant,
+    /// Whether both seats have been taken. From then on, nobody else can join.
+    started: bool,
+}
+
+/// Where a client is in the lobby.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Membership {
+    Queued,
+    Player { code: String, player: WhichPlayer },
+    Spectator { code: String },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LobbyError {
+    /// The client is already queued or in a room.
+    AlreadyInLobby,
+    NoSuchRoom,
+    RoomFull,
+    /// Seats cannot be taken once both players have joined, even if one has left.
+    GameStarted,
+    /// The client must be playing in a room to do that.
+    NotAPlayer,
+    /// The game waits for both players to join before anyone can play.
+    GameNotStarted,
+    /// Rematches can only be requested once the game is over.
+    GameNotOver,
+    /// A rematch needs both players, and one has left the room.
+    OpponentLeft,
+    Choice(ChoiceError),
+}
+
+impl Lobby {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            rooms: HashMap::new(),
+            members: HashMap::new(),
+            queue: VecDeque::new(),
+            rules: RuleSet::default(),
+            idle_timeout: Duration::from_secs(600),
+        }
+    }
+
+    pub fn room(&self, code: &str) -> Option<&Room> {
+        self.rooms.get(code)
+    }
+
+    pub fn membership(&self, client: ClientId) -> Option<&Membership> {
+        self.members.get(&client)
+    }
+
+    /// The room `client` is playing or spectating in.
+    pub fn room_of(&self, client: ClientId) -> Option<&Room> {
+        match self.members.get(&client) {
+            Some(&Membership::Player { ref code, .. }) | Some(&Membership::Spectator { ref code }) => self.rooms.get(code),
+            _ => None,
+        }
+    }
+
+    /// Opens a new room with `client` as player A, returning its code.
+    pub fn create_room(&mut self, client: ClientId, now: Instant) -> Result<String, LobbyError> {
+        self.check_not_in_lobby(client)?;
+
+        let code = self.open_room(now);
+        self.seat(client, &code, WhichPlayer::PlayerA);
+
+        Ok(code)
+    }
+
+    /// Takes the free seat in the room with `code`, before its game has started.
+    pub fn join_room(&mut self, client: ClientId, code: &str, now: Instant) -> Result<WhichPlayer, LobbyError> {
+        self.check_not_in_lobby(client)?;
+
+        let room = self.rooms.get_mut(code).ok_or(LobbyError::NoSuchRoom)?;
+        let player = match room.players.iter().position(Option::is_none) {
+            None => return Err(LobbyError::RoomFull),
+            Some(_) if room.started => return Err(LobbyError::GameStarted),
+            Some(0) => WhichPlayer::PlayerA,
+            Some(_) => WhichPlayer::PlayerB,
+        };
+        room.last_activity = now;
+
+        self.seat(client, code, player);
+
+        Ok(player)
+    }
+
+    pub fn spectate(&mut self, client: ClientId, code: &str, now: Instant) -> Result<(), LobbyError> {
+        self.check_not_in_lobby(client)?;
+
+        let room = self.rooms.get_mut(code).ok_or(LobbyError::NoSuchRoom)?;
+        room.spectators.push(client);
+        room.last_activity = now;
+
+        self.members.insert(client, Membership::Spectator { code: code.to_string() });
+
+        Ok(())
+    }
+
+    /// Waits for a random opponent.
+    ///
+    /// Returns the new room's code if someone was already waiting; they become player A.
+    pub fn enqueue(&mut self, client: ClientId, now: Instant) -> Result<Option<String>, LobbyError> {
+        self.check_not_in_lobby(client)?;
+
+        match self.queue.pop_front() {
+            Some(opponent) => {
+                let code = self.open_room(now);
+                self.seat(opponent, &code, WhichPlayer::PlayerA);
+                self.seat(client, &code, WhichPlayer::PlayerB);
+
+                Ok(Some(code))
+            },
+            None => {
+                self.queue.push_back(client);
+                self.members.insert(client, Membership::Queued);
+
+                Ok(None)
+            },
+        }
+    }
+
+    /// Makes a choice for the player `client` is seated as.
+    pub fn choose(&mut self, client: ClientId, choice: String, now: Instant) -> Result<ChoiceOutcome, LobbyError> {
+        let (room, player) = self.player_room(client)?;
+        room.last_activity = now;
+
+        room.game.process_choice(player, choice).map_err(LobbyError::Choice)
+    }
+
+    /// Gives up the current game, ending it in the opponent's favor.
+    pub fn resign(&mut self, client: ClientId, now: Instant) -> Result<ChoiceOutcome, LobbyError> {
+        let (room, player) = self.player_room(client)?;
+        room.last_activity = now;
+
+        room.game.resign(player).map_err(LobbyError::Choice)
+    }
+
+    /// Offers the opponent a draw, or accepts theirs if they offered first.
+    pub fn offer_draw(&mut self, client: ClientId, now: Instant) -> Result<ChoiceOutcome, LobbyError> {
+        let (room, player) = self.player_room(client)?;
+        room.last_activity = now;
+
+        room.game.offer_draw(player).map_err(LobbyError::Choice)
+    }
+
+    pub fn accept_draw(&mut self, client: ClientId, now: Instant) -> Result<ChoiceOutcome, LobbyError> {
+        let (room, player) = self.player_room(client)?;
+        room.last_activity = now;
+
+        room.game.accept_draw(player).map_err(LobbyError::Choice)
+    }
+
+    /// Asks for another game in the same room once the current one is over.
+    ///
+    /// Returns whether the rematch started, which happens once both players
+    /// have asked. Seats are never reopened, so a room that lost a player
+    /// cannot rematch.
+    pub fn request_rematch(&mut self, client: ClientId, now: Instant) -> Result<bool, LobbyError> {
+        let rules = self.rules;
+        let (room, player) = self.player_room(client)?;
+
+        if let Phase::GameOver(_, _) = room.game.phase {} else {
+            return Err(LobbyError::GameNotOver);
+        }
+        if room.players.iter().any(Option::is_none) {
+            return Err(LobbyError::OpponentLeft);
+        }
+
+        room.last_activity = now;
+        room.rematch_requested[seat_index(player)] = true;
+
+        if room.rematch_requested == [true, true] {
+            room.game = NZSCTwoPlayerGame::with_rules(rules);
+            room.rematch_requested = [false, false];
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Removes `client` from wherever they are, as on disconnect.
+    ///
+    /// A player who leaves a game in progress forfeits it, and their seat
+    /// stays empty. A room closes once it has no players left.
+    pub fn leave(&mut self, client: ClientId) {
+        match self.members.remove(&client) {
+            Some(Membership::Queued) => {
+                self.queue.retain(|&queued| queued != client);
+            },
+            Some(Membership::Player { code, player }) => {
+                let is_empty = match self.rooms.get_mut(&code) {
+                    Some(room) => {
+                        if room.started {
+                            // Fails harmlessly if the game is already over.
+                            let _ = room.game.forfeit(player);
+                        }
+                        room.players[seat_index(player)] = None;
+                        room.rematch_requested[seat_index(player)] = false;
+                        room.players.iter().all(Option::is_none)
+                    },
+                    None => false,
+                };
+
+                if is_empty {
+                    self.close_room(&code);
+                }
+            },
+            Some(Membership::Spectator { code }) => {
+                if let Some(room) = self.rooms.get_mut(&code) {
+                    room.spectators.retain(|&spectator| spectator != client);
+                }
+            },
+            None => {},
+        }
+    }
+
+    /// Closes every room idle for at least `idle_timeout`, returning them
+    /// so their former members can be told.
+    pub fn remove_idle_rooms(&mut self, now: Instant) -> Vec<Room> {
+        let idle_timeout = self.idle_timeout;
+        let idle: Vec<String> = self.rooms.values()
+            .filter(|room| now.duration_since(room.last_activity) >= idle_timeout)
+            .map(|room| room.code.clone())
+            .collect();
+
+        idle.iter().filter_map(|code| self.close_room(code)).collect()
+    }
+
+    fn check_not_in_lobby(&self, client: ClientId) -> Result<(), LobbyError> {
+        if self.members.contains_key(&client) {
+            Err(LobbyError::AlreadyInLobby)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn player_room(&mut self, client: ClientId) -> Result<(&mut Room, WhichPlayer), LobbyError> {
+        match self.members.get(&client) {
+            Some(&Membership::Player { ref code, player }) => {
+                let room = self.rooms.get_mut(code).expect("member's room exists");
+                if !room.started {
+                    return Err(LobbyError::GameNotStarted);
+                }
+
+                Ok((room, player))
+            },
+            _ => Err(LobbyError::NotAPlayer),
+        }
+    }
+
+    fn open_room(&mut self, now: Instant) -> String {
+        let code = loop {
+            let code: String = (0..CODE_LENGTH)
+                .map(|_| CODE_LETTERS[self.rng.below(CODE_LETTERS.len())] as char)
+                .collect();
+
+            if !self.rooms.contains_key(&code) {
+                break code;
+            }
+        };
+
+        self.rooms.insert(code.clone(), Room {
+            code: code.clone(),
+            game: NZSCTwoPlayerGame::with_rules(self.rules),
+            players: [None, None],
+            spectators: vec![],
+            rematch_requested: [false, false],
+            last_activity: now,
+            started: false,
+        });
+
+        code
+    }
+
+    fn seat(&mut self, client: ClientId, code: &str, player: WhichPlayer) {
+        let room = self.rooms.get_mut(code).expect("room exists");
+        room.players[seat_index(player)] = Some(client);
+        if room.players.iter().all(Option::is_some) {
+            room.started = true;
+        }
+
+        self.members.insert(client, Membership::Player { code: code.to_string(), player });
+    }
+
+    fn close_room(&mut self, code: &str) -> Option<Room> {
+        let room = self.rooms.remove(code)?;
+
+        for &client in room.players.iter().flatten().chain(&room.spectators) {
+            self.members.remove(&client);
+        }
+
+        Some(room)
+    }
+}
+
+impl Room {
+    /// Whether both seats have been taken, closing the room to new players.
+    pub fn has_started(&self) -> bool {
+        self.started
+    }
+
+    /// Everyone who should hear about changes to this room.
+    pub fn members(&self) -> Vec<ClientId> {
+        self.players.iter().flatten().chain(&self.spectators).cloned().collect()
+    }
+}
+
+impl fmt::Display for LobbyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LobbyError::AlreadyInLobby => write!(f, "Already queued or in a room"),
+            LobbyError::NoSuchRoom => write!(f, "No room has that code"),
+            LobbyError::RoomFull => write!(f, "Room is full"),
+            LobbyError::GameStarted => write!(f, "Game has already started"),
+            LobbyError::NotAPlayer => write!(f, "Not playing in a room"),
+            LobbyError::GameNotStarted => write!(f, "Game has not started"),
+            LobbyError::GameNotOver => write!(f, "Game is not over"),
+            LobbyError::OpponentLeft => write!(f, "Opponent has left, so there can be no rematch"),
+            LobbyError::Choice(ref error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for LobbyError {}
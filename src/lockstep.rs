@@ -0,0 +1,246 @@
+//! Playing over a direct connection, with no server.
+//!
+//! Each peer keeps its own `NZSCTwoPlayerGame` and plays one of its seats.
+//! Peers send each other their choices, apply both in the same order, and
+//! then compare `StateHash`es of the resulting `Phase`, so a desync is
+//! caught after the round that caused it.
+//!
+//! # Protocol
+//!
+//! Peers exchange lines of text:
+//!
+//! - `hello <seat> <rules>` is sent once by each peer on connecting, where
+//!   `<seat>` is `A` or `B` and `<rules>` is the `RuleSet`'s hash. The peers
+//!   must have different seats and the same rules.
+//! - `choose <choice>` is sent each round by the peer of every player who
+//!   still has to choose, exactly as it would be passed to `process_choice`.
+//!   Once a peer has both, it applies A's choice and then B's.
+//! - `hash <phase>` follows every round, with the hash of the new `Phase`.
+//!
+//! Hashes are written as 16 lowercase hex digits.
+//!
+//! Choices travel in the clear, so a peer could wait for its opponent's
+//! choice before picking its own. Where that matters, exchange choices
+//! with `commit_reveal` instead.
+
+use super::rules::RuleSet;
+use super::state_hash::StateHash;
+use super::two_player_game::{
+    ChoiceError,
+    ChoiceOutcome,
+    NZSCTwoPlayerGame,
+    Phase,
+    WhichPlayer,
+};
+use std::error::Error;
+use std::fmt;
+use std::io::{ self, BufRead, BufReader, Write, };
+use std::net::{ TcpStream, ToSocketAddrs, };
+
+/// One side of a lockstep game.
+pub struct LockstepPeer {
+    game: NZSCTwoPlayerGame,
+    player: WhichPlayer,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+#[derive(Debug)]
+pub enum LockstepError {
+    Io(io::Error),
+    /// The other peer hung up.
+    Disconnected,
+    /// The other peer sent something that does not follow the protocol.
+    UnexpectedMessage(String),
+    /// Both peers claimed the same seat.
+    SameSeat,
+    /// The peers are playing by different rules.
+    RulesMismatch,
+    /// The peers' games no longer agree.
+    Desync {
+        local: u64,
+        remote: u64,
+    },
+    /// This peer's player has to choose this round, but no choice was given.
+    MissingChoice,
+    /// Choices are sent as single lines, so cannot contain line breaks.
+    MultilineChoice,
+    Choice(ChoiceError),
+}
+
+impl LockstepPeer {
+    /// Connects to a peer that is listening, and plays as `player`.
+    pub fn connect<A: ToSocketAddrs>(address: A, player: WhichPlayer, rules: RuleSet) -> Result<Self, LockstepError> {
+        Self::new(TcpStream::connect(address)?, player, rules)
+    }
+
+    /// Starts playing as `player` over an open connection, such as one
+    /// accepted from a `TcpListener`.
+    pub fn new(stream: TcpStream, player: WhichPlayer, rules: RuleSet) -> Result<Self, LockstepError> {
+        let mut peer = Self {
+            game: NZSCTwoPlayerGame::with_rules(rules),
+            player,
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        };
+
+        let rules_hash = rules.state_hash();
+        peer.send(&format!("hello {} {:016x}", seat_name(player), rules_hash))?;
+
+        let line = peer.receive()?;
+        let remote_hash = {
+            let mut words = line.split(' ');
+            match (words.next(), words.next(), words.next().and_then(parse_hash), words.next()) {
+                (Some("hello"), Some(seat), Some(hash), None) if seat == "A" || seat == "B" => {
+                    if seat == seat_name(player) {
+                        return Err(LockstepError::SameSeat);
+                    }
+                    hash
+                },
+                _ => return Err(LockstepError::UnexpectedMessage(line)),
+            }
+        };
+
+        if remote_hash != rules_hash {
+            return Err(LockstepError::RulesMismatch);
+        }
+
+        Ok(peer)
+    }
+
+    pub fn game(&self) -> &NZSCTwoPlayerGame {
+        &self.game
+    }
+
+    pub fn player(&self) -> WhichPlayer {
+        self.player
+    }
+
+    /// Whether this peer has to give a choice to the next `play_round`.
+    pub fn needs_choice(&self) -> bool {
+        self.game.is_waiting_on(self.player)
+    }
+
+    /// Plays one round: sends `choice` if this peer's player still has to
+    /// choose, waits for the opponent's if they do, applies both, and checks
+    /// that both peers ended up in the same state.
+    ///
+    /// `choice` is ignored when `needs_choice` is false. Returns the outcome
+    /// of every choice applied, A's first.
+    pub fn play_round(&mut self, choice: Option<String>) -> Result<Vec<(WhichPlayer, ChoiceOutcome)>, LockstepError> {
+        if let Phase::GameOver(_, _) = self.game.phase {
+            return Err(LockstepError::Choice(ChoiceError::GameOver));
+        }
+
+        let local_choice = if self.needs_choice() {
+            let choice = choice.ok_or(LockstepError::MissingChoice)?;
+            if choice.contains('\n') || choice.contains('\r') {
+                return Err(LockstepError::MultilineChoice);
+            }
+
+            self.send(&format!("choose {}", choice))?;
+            Some(choice)
+        } else {
+            None
+        };
+
+        let opponent = self.player.opponent();
+        let remote_choice = if self.game.is_waiting_on(opponent) {
+            let line = self.receive()?;
+            match line.strip_prefix("choose ") {
+                Some(choice) => Some(choice.to_string()),
+                None => return Err(LockstepError::UnexpectedMessage(line)),
+            }
+        } else {
+            None
+        };
+
+        let (a_choice, b_choice) = match self.player {
+            WhichPlayer::PlayerA => (local_choice, remote_choice),
+            WhichPlayer::PlayerB => (remote_choice, local_choice),
+        };
+
+        let choices = a_choice.map(|choice| (WhichPlayer::PlayerA, choice)).into_iter()
+            .chain(b_choice.map(|choice| (WhichPlayer::PlayerB, choice)));
+
+        let mut outcomes = vec![];
+        for (player, result) in self.game.process_simultaneous_choices(choices) {
+            outcomes.push((player, result.map_err(LockstepError::Choice)?));
+        }
+
+        self.check_sync()?;
+
+        Ok(outcomes)
+    }
+
+    /// Exchanges hashes of the current phase with the other peer.
+    fn check_sync(&mut self) -> Result<(), LockstepError> {
+        let local = self.game.phase.state_hash();
+        self.send(&format!("hash {:016x}", local))?;
+
+        let line = self.receive()?;
+        let remote = line.strip_prefix("hash ").and_then(parse_hash);
+
+        match remote {
+            Some(remote) if remote == local => Ok(()),
+            Some(remote) => Err(LockstepError::Desync { local, remote }),
+            None => Err(LockstepError::UnexpectedMessage(line)),
+        }
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), LockstepError> {
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<String, LockstepError> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(LockstepError::Disconnected);
+        }
+
+        Ok(line.trim_end_matches(['\n', '\r']).to_string())
+    }
+}
+
+fn seat_name(player: WhichPlayer) -> &'static str {
+    match player {
+        WhichPlayer::PlayerA => "A",
+        WhichPlayer::PlayerB => "B",
+    }
+}
+
+fn parse_hash(hex: &str) -> Option<u64> {
+    if hex.len() == 16 {
+        u64::from_str_radix(hex, 16).ok()
+    } else {
+        None
+    }
+}
+
+impl From<io::Error> for LockstepError {
+    fn from(error: io::Error) -> Self {
+        LockstepError::Io(error)
+    }
+}
+
+impl fmt::Display for LockstepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LockstepError::Io(ref error) => write!(f, "{}", error),
+            LockstepError::Disconnected => write!(f, "Peer disconnected"),
+            LockstepError::UnexpectedMessage(ref line) => write!(f, "Unexpected message from peer: {:?}", line),
+            LockstepError::SameSeat => write!(f, "Both peers are playing the same seat"),
+            LockstepError::RulesMismatch => write!(f, "Peers are playing by different rules"),
+            LockstepError::Desync { local, remote } => write!(f, "Games out of sync: local state {:016x}, peer state {:016x}", local, remote),
+            LockstepError::MissingChoice => write!(f, "A choice is needed this round"),
+            LockstepError::MultilineChoice => write!(f, "Choices cannot contain line breaks"),
+            LockstepError::Choice(ref error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for LockstepError {}
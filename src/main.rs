@@ -0,0 +1,254 @@
+//! Plays a match in the terminal, either hot-seat or against a bot.
+//!
+//! Usage: `nzsc2p [--bot random|greedy|streak|mcts] [--seed N]`
+//!
+//! Besides choices, players can type `/resign`, or `/draw` to offer or accept a draw.
+
+extern crate nzsc2p;
+
+use nzsc2p::events::Event;
+use nzsc2p::mcts::MctsStrategy;
+use nzsc2p::strategies::{
+    Strategy,
+    RandomStrategy,
+    GreedyStrategy,
+    StreakAwareStrategy,
+};
+use nzsc2p::two_player_game::{
+    NZSCTwoPlayerGame,
+    Phase,
+    WhichPlayer,
+};
+use nzsc2p::views::PlayerView;
+use std::env;
+use std::fmt::Display;
+use std::io::{ self, BufRead, Write, };
+use std::process;
+
+const USAGE: &str = "Usage: nzsc2p [--bot random|greedy|streak|mcts] [--seed N]";
+
+fn main() {
+    let mut bot: Option<Box<dyn Strategy>> = None;
+    let mut bot_name: Option<String> = None;
+    let mut seed = 0;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bot" => bot_name = Some(args.next().unwrap_or_else(|| exit_with_usage())),
+            "--seed" => {
+                seed = args.next()
+                    .and_then(|seed| seed.parse().ok())
+                    .unwrap_or_else(|| exit_with_usage());
+            },
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return;
+            },
+            _ => exit_with_usage(),
+        }
+    }
+
+    if let Some(name) = bot_name {
+        bot = Some(match name.as_str() {
+            "random" => Box::new(RandomStrategy::new(seed)),
+            "greedy" => Box::new(GreedyStrategy),
+            "streak" => Box::new(StreakAwareStrategy),
+            "mcts" => Box::new(MctsStrategy::new(seed, 2000)),
+            _ => exit_with_usage(),
+        });
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut game = NZSCTwoPlayerGame::new();
+    let mut last_events: Vec<Event> = vec![];
+    let hot_seat = bot.is_none();
+
+    loop {
+        if let Phase::GameOver(a_points, b_points) = game.phase {
+            print_events(&last_events);
+            println!("Game over! Player A: {}, Player B: {}.", a_points, b_points);
+            match game.winner() {
+                Some(winner) => println!("{} wins.", player_name(winner)),
+                None => println!("It's a draw."),
+            }
+            return;
+        }
+
+        let mut events = vec![];
+
+        for &player in &[WhichPlayer::PlayerA, WhichPlayer::PlayerB] {
+            if !game.is_waiting_on(player) {
+                continue;
+            }
+            if let Phase::GameOver(_, _) = game.phase {
+                break;
+            }
+
+            let view = game.view_for(player);
+            let choice = match (player, bot.as_mut()) {
+                (WhichPlayer::PlayerB, Some(bot)) => {
+                    bot.choose(&view)
+                        .or_else(|| game.cheapest_penalized_choice(player))
+                        .map(|choice| choice.to_string())
+                        .unwrap_or_default()
+                },
+                _ => {
+                    if hot_seat {
+                        prompt(&format!("Pass to {} and press Enter.", player_name(player)));
+                        if lines.next().is_none() {
+                            return;
+                        }
+                        clear_screen();
+                    }
+
+                    print_events(&last_events);
+                    print_view(&view);
+                    prompt("Your choice: ");
+
+                    let line = match lines.next() {
+                        Some(Ok(line)) => line,
+                        _ => return,
+                    };
+                    if hot_seat {
+                        clear_screen();
+                    }
+
+                    line.trim().to_string()
+                },
+            };
+
+            let result = match choice.as_str() {
+                "/resign" => game.resign(player),
+                "/draw" => game.offer_draw(player),
+                _ => game.process_choice(player, choice),
+            };
+
+            match result {
+                Ok(outcome) => {
+                    // Hot-seat players see their penalty before the screen is handed over.
+                    if let (true, Some(penalty)) = (hot_seat, outcome.penalty) {
+                        println!("Penalized: {}.", penalty);
+                    }
+
+                    events.extend(outcome.events);
+                },
+                Err(error) => println!("{}", error),
+            }
+        }
+
+        last_events = events;
+    }
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn prompt(message: &str) {
+    print!("{}", message);
+    io::stdout().flush().expect("write to stdout");
+}
+
+fn clear_screen() {
+    print!("\x1B[2J\x1B[H");
+}
+
+fn player_name(player: WhichPlayer) -> &'static str {
+    match player {
+        WhichPlayer::PlayerA => "Player A",
+        WhichPlayer::PlayerB => "Player B",
+    }
+}
+
+fn list<T: Display>(items: &[T]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.iter().map(|item| item.to_string()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+fn streak<T: Display>(repeated: Option<T>, times: u8) -> String {
+    match repeated {
+        Some(repeated) => format!("{} x{}", repeated, times),
+        None => "none".to_string(),
+    }
+}
+
+/// Prints what happened last turn, leaving out picks so hot-seat players cannot peek.
+fn print_events(events: &[Event]) {
+    for event in events {
+        match *event {
+            Event::CharacterSelected { .. } | Event::BoosterSelected { .. } | Event::MoveSelected { .. } => {},
+            Event::CharactersTied(character) => println!("Both players chose {}.", character),
+            Event::HeadstartAwarded { player, points } => {
+                println!("{} gets a headstart of {}.", player_name(player), points);
+            },
+            Event::MovesRevealed { a_move, b_move, a_points, b_points } => {
+                println!("Player A played {} (+{}), Player B played {} (+{}).", a_move, a_points, b_move, b_points);
+            },
+            Event::MoveDestroyed { player, destroyed_move } => {
+                println!("{}'s {} was destroyed.", player_name(player), destroyed_move);
+            },
+            Event::SingleUseConsumed { player, consumed_move } => {
+                println!("{} used up {}.", player_name(player), consumed_move);
+            },
+            Event::PenaltyApplied { player, penalty } => {
+                println!("{} was penalized: {}.", player_name(player), penalty);
+            },
+            Event::Resigned { player } => println!("{} resigned.", player_name(player)),
+            Event::Forfeited { player } => println!("{} forfeited.", player_name(player)),
+            Event::DrawOffered { player } => println!("{} offers a draw. Type /draw to accept.", player_name(player)),
+            Event::DrawAccepted { player } => println!("{} accepted the draw.", player_name(player)),
+            Event::SuddenDeathReset { points } => {
+                println!("Sudden death! Both players go back to {} points.", points);
+            },
+            Event::GameOver { .. } => {},
+        }
+    }
+}
+
+fn print_view(view: &PlayerView) {
+    println!();
+    for &player in &[WhichPlayer::PlayerA, WhichPlayer::PlayerB] {
+        let you = if player == view.player { " (you)" } else { "" };
+
+        match view.phase {
+            Phase::CharacterChoosing(ref a, ref b) => {
+                let p = if player == WhichPlayer::PlayerA { a } else { b };
+                println!("{}{}: {} points, {} waits", player_name(player), you, p.points, p.waits);
+                println!("  Character streak: {}", streak(p.character_streak.repeated_character, p.character_streak.times));
+            },
+            Phase::BoosterChoosing(ref a, ref b) => {
+                let p = if player == WhichPlayer::PlayerA { a } else { b };
+                println!("{}{}: {} points, {} waits", player_name(player), you, p.points, p.waits);
+                println!("  Character: {}", p.character);
+            },
+            Phase::MoveChoosing(ref a, ref b) => {
+                let p = if player == WhichPlayer::PlayerA { a } else { b };
+                println!("{}{}: {} points, {} waits", player_name(player), you, p.points, p.waits);
+                println!("  Character: {}, booster: {}", p.character, p.booster);
+                println!("  Move streak: {}", streak(p.move_streak.repeated_move, p.move_streak.times));
+                println!("  Destroyed moves: {}", list(&p.destroyed_moves));
+                println!("  Available moves: {}", list(&p.available_moves(&view.rules)));
+            },
+            Phase::GameOver(_, _) => {},
+        }
+    }
+
+    if view.opponent_has_chosen {
+        println!("Your opponent has chosen.");
+    }
+    if view.draw_offer == Some(view.player.opponent()) {
+        println!("Your opponent offers a draw. Type /draw to accept, or choose to decline.");
+    }
+    let legal_choices = view.legal_choices();
+    if legal_choices.is_empty() {
+        println!("You have no legal choices left, so any choice will be penalized.");
+    } else {
+        println!("Legal choices: {}", list(&legal_choices));
+    }
+}
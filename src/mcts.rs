@@ -0,0 +1,263 @@
+//! Monte Carlo tree search over whole games.
+//!
+//! Both players choose simultaneously, so each tree node keeps separate
+//! statistics for each player's choices (decoupled UCT) and children are
+//! keyed by the pair of choices made.
+
+use super::choices::Choice;
+use super::random::Rng;
+use super::strategies::Strategy;
+use super::views::PlayerView;
+use super::two_player_game::{
+    NZSCTwoPlayerGame,
+    Phase,
+    WhichPlayer,
+};
+use std::time::{ Duration, Instant, };
+
+/// A bot that plays out many random continuations of the game and picks the
+/// choice that wins most often.
+///
+/// Given the same seed and no `time_limit`, it always makes the same choices.
+pub struct MctsStrategy {
+    rng: Rng,
+    /// How many playouts to run per choice.
+    pub iterations: u32,
+    /// Stop early once this much time has passed. Makes choices depend on machine speed.
+    pub time_limit: Option<Duration>,
+    /// How strongly to favor rarely tried choices over ones that have done well.
+    pub exploration: f64,
+    /// Playouts that last this many rounds are scored by the points so far.
+    pub max_playout_rounds: u32,
+}
+
+/// Indices into each player's stats, or `None` for a player who is not being waited on.
+type JointChoice = (Option<usize>, Option<usize>);
+
+struct ChoiceStats {
+    choice: Choice,
+    visits: u32,
+    /// Sum of this player's rewards, each between 0 and 1.
+    reward: f64,
+}
+
+struct Node {
+    a_stats: Vec<ChoiceStats>,
+    b_stats: Vec<ChoiceStats>,
+    visits: u32,
+    children: Vec<(JointChoice, usize)>,
+}
+
+impl MctsStrategy {
+    pub fn new(seed: u64, iterations: u32) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            iterations,
+            time_limit: None,
+            exploration: 2f64.sqrt(),
+            max_playout_rounds: 100,
+        }
+    }
+
+    /// Searches from `game` and returns the best choice for `player`.
+    ///
+    /// `game` is used as is, so give it a view's phase to avoid peeking at the opponent's pending choice.
+    pub fn search(&mut self, game: &NZSCTwoPlayerGame, player: WhichPlayer) -> Option<Choice> {
+        if game.legal_choices(player).is_empty() {
+            return None;
+        }
+
+        let start = Instant::now();
+        let mut nodes = vec![Node::new(game)];
+
+        for _ in 0..self.iterations {
+            if let Some(time_limit) = self.time_limit {
+                if start.elapsed() >= time_limit {
+                    break;
+                }
+            }
+
+            self.run_iteration(game, &mut nodes);
+        }
+
+        let root_stats = match player {
+            WhichPlayer::PlayerA => &nodes[0].a_stats,
+            WhichPlayer::PlayerB => &nodes[0].b_stats,
+        };
+
+        let mut best = &root_stats[0];
+        for stats in root_stats {
+            if stats.visits > best.visits {
+                best = stats;
+            }
+        }
+
+        Some(best.choice)
+    }
+
+    fn run_iteration(&mut self, root: &NZSCTwoPlayerGame, nodes: &mut Vec<Node>) {
+        let mut game = root.clone();
+        let mut path: Vec<(usize, Option<usize>, Option<usize>)> = vec![];
+        let mut current = 0;
+
+        loop {
+            if let Phase::GameOver(_, _) = game.phase {
+                break;
+            }
+
+            let a_index = self.select(&nodes[current].a_stats, nodes[current].visits);
+            let b_index = self.select(&nodes[current].b_stats, nodes[current].visits);
+            if a_index.is_none() && b_index.is_none() {
+                break;
+            }
+
+            if let Some(a_index) = a_index {
+                let _ = game.choose(WhichPlayer::PlayerA, nodes[current].a_stats[a_index].choice);
+            }
+            if let Some(b_index) = b_index {
+                let _ = game.choose(WhichPlayer::PlayerB, nodes[current].b_stats[b_index].choice);
+            }
+
+            path.push((current, a_index, b_index));
+
+            let key = (a_index, b_index);
+            let existing = nodes[current].children.iter()
+                .find(|&&(child_key, _)| child_key == key)
+                .map(|&(_, child)| child);
+
+            match existing {
+                Some(child) => {
+                    current = child;
+                },
+                None => {
+                    let child = nodes.len();
+                    nodes.push(Node::new(&game));
+                    nodes[current].children.push((key, child));
+                    break;
+                },
+            }
+        }
+
+        let a_reward = self.playout(&mut game);
+
+        for (node, a_index, b_index) in path {
+            let node = &mut nodes[node];
+            node.visits += 1;
+
+            if let Some(a_index) = a_index {
+                node.a_stats[a_index].visits += 1;
+                node.a_stats[a_index].reward += a_reward;
+            }
+            if let Some(b_index) = b_index {
+                node.b_stats[b_index].visits += 1;
+                node.b_stats[b_index].reward += 1.0 - a_reward;
+            }
+        }
+    }
+
+    /// Picks a choice by UCB1, trying every choice once first.
+    fn select(&mut self, stats: &[ChoiceStats], parent_visits: u32) -> Option<usize> {
+        if stats.is_empty() {
+            return None;
+        }
+
+        let untried: Vec<usize> = (0..stats.len()).filter(|&i| stats[i].visits == 0).collect();
+        if !untried.is_empty() {
+            return Some(untried[self.rng.below(untried.len())]);
+        }
+
+        let log_visits = (parent_visits.max(1) as f64).ln();
+        let mut best = 0;
+        let mut best_score = f64::MIN;
+
+        for (i, stats) in stats.iter().enumerate() {
+            let visits = stats.visits as f64;
+            let score = stats.reward / visits + self.exploration * (log_visits / visits).sqrt();
+
+            if score > best_score {
+                best = i;
+                best_score = score;
+            }
+        }
+
+        Some(best)
+    }
+
+    /// Plays random choices from `choices_for` until the game ends, returning player A's reward.
+    fn playout(&mut self, game: &mut NZSCTwoPlayerGame) -> f64 {
+        for _ in 0..self.max_playout_rounds {
+            if let Phase::GameOver(_, _) = game.phase {
+                break;
+            }
+
+            for &player in &[WhichPlayer::PlayerA, WhichPlayer::PlayerB] {
+                let choices = choices_for(game, player);
+
+                if !choices.is_empty() {
+                    let choice = choices[self.rng.below(choices.len())];
+                    let _ = game.choose(player, choice);
+                }
+            }
+        }
+
+        let (a_points, b_points) = match game.phase {
+            Phase::CharacterChoosing(ref a, ref b) => (a.points, b.points),
+            Phase::BoosterChoosing(ref a, ref b) => (a.points, b.points),
+            Phase::MoveChoosing(ref a, ref b) => (a.points, b.points),
+            Phase::GameOver(a, b) => {
+                return if a > b { 1.0 } else { 0.0 };
+            },
+        };
+
+        // Unfinished: lean towards whoever is ahead.
+        let points_to_win = game.rules().points_to_win.max(1) as f64;
+        let lead = (a_points as f64 - b_points as f64) / points_to_win;
+
+        (0.5 + lead / 2.0).clamp(0.0, 1.0)
+    }
+}
+
+impl Strategy for MctsStrategy {
+    fn choose(&mut self, view: &PlayerView) -> Option<Choice> {
+        let game = NZSCTwoPlayerGame::from_phase(view.phase.clone(), view.rules);
+
+        self.search(&game, view.player)
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+}
+
+impl Node {
+    fn new(game: &NZSCTwoPlayerGame) -> Self {
+        let stats = |player| {
+            choices_for(game, player)
+                .into_iter()
+                .map(|choice| ChoiceStats {
+                    choice,
+                    visits: 0,
+                    reward: 0.0,
+                })
+                .collect()
+        };
+
+        Self {
+            a_stats: stats(WhichPlayer::PlayerA),
+            b_stats: stats(WhichPlayer::PlayerB),
+            visits: 0,
+            children: vec![],
+        }
+    }
+}
+
+/// `player`'s legal choices, or the forced penalized one if they have none.
+fn choices_for(game: &NZSCTwoPlayerGame, player: WhichPlayer) -> Vec<Choice> {
+    let choices = game.legal_choices(player);
+
+    if choices.is_empty() {
+        game.cheapest_penalized_choice(player).into_iter().collect()
+    } else {
+        choices
+    }
+}
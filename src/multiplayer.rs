@@ -0,0 +1,440 @@
+//! Free-for-all NZSC for three to six players.
+//!
+//! Players are numbered from 0 in seating order. The two-player rules carry
+//! over with every opponent in place of the one:
+//!
+//! - Characters are chosen again, with streaks counted, only when everyone
+//!   chose the same one. Otherwise each player's headstart is the sum of
+//!   their headstarts against every other character at the table.
+//! - Each player scores their move against every other player's move.
+//! - A destructive move destroys the move of every other player, unless
+//!   that move was single-use and so used up anyway.
+//! - A player who runs out of waits gives a point to every opponent, so
+//!   they fall one point behind everyone, as in the two-player game.
+//! - Once anyone reaches `RuleSet::points_to_win`, the game ends if they
+//!   have the highest score on their own. If the highest score is shared,
+//!   everyone who reached `points_to_win` is set back to
+//!   `RuleSet::sudden_death_points` and play goes on.
+
+use super::choices::Choice;
+use super::events::{ DisplayAsDebug, debug_struct, };
+use super::players::{
+    CharacterlessPlayer,
+    BoosterlessPlayer,
+    MovelessPlayer,
+};
+use super::rules::RuleSet;
+use super::seats::{
+    Seat,
+    resolve_boosters,
+};
+use super::two_player_game::{
+    ChoiceError,
+    Penalty,
+    PenaltyReason,
+};
+use nzsc_core::{
+    characters::Character,
+    boosters::Booster,
+    moves::{
+        Move,
+        SINGLE_USE_MOVES,
+        DESTRUCTIVE_MOVES,
+    },
+    outcomes,
+};
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize, };
+use std::fmt;
+
+pub const MIN_PLAYERS: usize = 3;
+pub const MAX_PLAYERS: usize = 6;
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NZSCMultiplayerGame {
+    pub phase: MultiplayerPhase,
+    rules: RuleSet,
+}
+
+/// Like `Phase`, with one player per seat.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MultiplayerPhase {
+    CharacterChoosing(Vec<CharacterlessPlayer>),
+    BoosterChoosing(Vec<BoosterlessPlayer>),
+    MoveChoosing(Vec<MovelessPlayer>),
+    GameOver(Vec<u8>),
+}
+
+/// Something that happened while processing a choice, like `Event`.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MultiplayerEvent {
+    CharacterSelected {
+        player: usize,
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::CharacterDef"))]
+        character: Character,
+    },
+    /// Everyone chose the same character and must choose again.
+    CharactersTied(
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::CharacterDef"))]
+        Character
+    ),
+    HeadstartAwarded {
+        player: usize,
+        points: u8,
+    },
+    BoosterSelected {
+        player: usize,
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::BoosterDef"))]
+        booster: Booster,
+    },
+    MoveSelected {
+        player: usize,
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::MoveDef"))]
+        selected_move: Move,
+    },
+    /// Everyone's moves were revealed and scored, in seating order.
+    MovesRevealed {
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::vec_move"))]
+        moves: Vec<Move>,
+        points: Vec<u8>,
+    },
+    /// A player's move was destroyed by someone else's destructive move.
+    MoveDestroyed {
+        player: usize,
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::MoveDef"))]
+        destroyed_move: Move,
+    },
+    SingleUseConsumed {
+        player: usize,
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::MoveDef"))]
+        consumed_move: Move,
+    },
+    /// A player made a penalized choice.
+    ///
+    /// `Penalty::point_to_opponent` means every opponent got a point.
+    PenaltyApplied {
+        player: usize,
+        penalty: Penalty,
+    },
+    /// The highest score was shared, so everyone who reached the winning score was set back to this one.
+    SuddenDeathReset {
+        points: u8,
+    },
+    /// The game ended with these scores, in seating order.
+    GameOver {
+        points: Vec<u8>,
+    },
+}
+
+/// What happened to a choice that was not rejected, like `ChoiceOutcome`.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MultiplayerOutcome {
+    pub penalty: Option<Penalty>,
+    pub events: Vec<MultiplayerEvent>,
+}
+
+impl NZSCMultiplayerGame {
+    /// # Panics
+    ///
+    /// Panics unless `players` is between `MIN_PLAYERS` and `MAX_PLAYERS`.
+    pub fn new(players: usize) -> Self {
+        Self::with_rules(players, RuleSet::default())
+    }
+
+    /// # Panics
+    ///
+    /// Panics unless `players` is between `MIN_PLAYERS` and `MAX_PLAYERS`.
+    pub fn with_rules(players: usize, rules: RuleSet) -> Self {
+        assert!(
+            (MIN_PLAYERS..=MAX_PLAYERS).contains(&players),
+            "NZSCMultiplayerGame needs {} to {} players, not {}", MIN_PLAYERS, MAX_PLAYERS, players
+        );
+
+        Self {
+            phase: MultiplayerPhase::CharacterChoosing(vec![CharacterlessPlayer::with_rules(&rules); players]),
+            rules,
+        }
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.scores().len()
+    }
+
+    /// Everyone's points, in seating order.
+    pub fn scores(&self) -> Vec<u8> {
+        match self.phase {
+            MultiplayerPhase::CharacterChoosing(ref players) => players.iter().map(|p| p.points).collect(),
+            MultiplayerPhase::BoosterChoosing(ref players) => players.iter().map(|p| p.points).collect(),
+            MultiplayerPhase::MoveChoosing(ref players) => players.iter().map(|p| p.points).collect(),
+            MultiplayerPhase::GameOver(ref points) => points.clone(),
+        }
+    }
+
+    /// Who won, or `None` if the game is still going.
+    pub fn winner(&self) -> Option<usize> {
+        match self.phase {
+            MultiplayerPhase::GameOver(ref points) => {
+                let top = points.iter().max()?;
+                points.iter().position(|p| p == top)
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether `player` still has to choose this phase.
+    pub fn is_waiting_on(&self, player: usize) -> bool {
+        self.seat(player).is_waiting()
+    }
+
+    fn seat(&self, player: usize) -> Seat<'_> {
+        match self.phase {
+            MultiplayerPhase::CharacterChoosing(ref players) => Seat::CharacterChoosing(&players[player]),
+            MultiplayerPhase::BoosterChoosing(ref players) => Seat::BoosterChoosing(&players[player]),
+            MultiplayerPhase::MoveChoosing(ref players) => Seat::MoveChoosing(&players[player]),
+            MultiplayerPhase::GameOver(_) => Seat::GameOver,
+        }
+    }
+
+    /// Parses `choice` according to the current phase and processes it, like
+    /// `NZSCTwoPlayerGame::process_choice`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no player `chooser`.
+    pub fn process_choice(&mut self, chooser: usize, choice: String) -> Result<MultiplayerOutcome, ChoiceError> {
+        let seat = self.seat(chooser);
+        let parsed = seat.parse_choice(&choice);
+        let reason = seat.classify_choice(parsed, &self.rules)?;
+
+        Ok(self.resolve(chooser, parsed, reason))
+    }
+
+    /// Processes a typed choice, like `NZSCTwoPlayerGame::choose`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no player `chooser`.
+    pub fn choose(&mut self, chooser: usize, choice: Choice) -> Result<MultiplayerOutcome, ChoiceError> {
+        let reason = self.seat(chooser).classify_choice(Some(choice), &self.rules)?;
+
+        Ok(self.resolve(chooser, Some(choice), reason))
+    }
+
+    /// Applies a classified choice: the penalty for `reason` if there is one, otherwise `choice`.
+    fn resolve(&mut self, chooser: usize, choice: Option<Choice>, reason: Option<PenaltyReason>) -> MultiplayerOutcome {
+        let rules = self.rules;
+        let mut events = vec![];
+        let mut penalty = None;
+
+        if let Some(reason) = reason {
+            let point = match self.phase {
+                MultiplayerPhase::CharacterChoosing(ref mut players) => players[chooser].penalize(rules.penalty(reason)),
+                MultiplayerPhase::BoosterChoosing(ref mut players) => players[chooser].penalize(rules.penalty(reason)),
+                MultiplayerPhase::MoveChoosing(ref mut players) => players[chooser].penalize(rules.penalty(reason)),
+                MultiplayerPhase::GameOver(_) => unreachable!(),
+            };
+
+            if point > 0 {
+                let mut scores = self.scores();
+                for (player, score) in scores.iter_mut().enumerate() {
+                    if player != chooser {
+                        *score += point;
+                    }
+                }
+                self.set_scores(&scores);
+            }
+            penalty = Some(reason.to_penalty(&rules, point > 0));
+        } else if let Some(choice) = choice {
+            let new_phase = match self.phase {
+                MultiplayerPhase::CharacterChoosing(ref mut players) => {
+                    let character = choice.character().expect("classified as a character");
+                    events.push(MultiplayerEvent::CharacterSelected { player: chooser, character });
+                    players[chooser].selected_character = Some(character);
+
+                    resolve_characters(players, &mut events)
+                },
+                MultiplayerPhase::BoosterChoosing(ref mut players) => {
+                    let booster = choice.booster().expect("classified as a booster");
+                    events.push(MultiplayerEvent::BoosterSelected { player: chooser, booster });
+                    players[chooser].selected_booster = Some(booster);
+
+                    resolve_boosters(players).map(MultiplayerPhase::MoveChoosing)
+                },
+                MultiplayerPhase::MoveChoosing(ref mut players) => {
+                    let selected_move = choice.move_().expect("classified as a move");
+                    events.push(MultiplayerEvent::MoveSelected { player: chooser, selected_move });
+                    players[chooser].selected_move = Some(selected_move);
+
+                    resolve_moves(players, &mut events);
+                    None
+                },
+                MultiplayerPhase::GameOver(_) => unreachable!(),
+            };
+
+            if let Some(new_phase) = new_phase {
+                self.phase = new_phase;
+            }
+        }
+
+        self.settle_scores(&mut events);
+
+        if let Some(penalty) = penalty {
+            events.push(MultiplayerEvent::PenaltyApplied { player: chooser, penalty });
+        }
+        if let MultiplayerPhase::GameOver(ref points) = self.phase {
+            events.push(MultiplayerEvent::GameOver { points: points.clone() });
+        }
+
+        MultiplayerOutcome {
+            penalty,
+            events,
+        }
+    }
+
+    /// Ends the game, or starts sudden death, once someone has reached `points_to_win`.
+    fn settle_scores(&mut self, events: &mut Vec<MultiplayerEvent>) {
+        let rules = self.rules;
+        let mut scores = self.scores();
+        let top = scores.iter().cloned().max().unwrap_or(0);
+
+        if let MultiplayerPhase::GameOver(_) = self.phase {
+            return;
+        }
+        if top < rules.points_to_win {
+            return;
+        }
+
+        if scores.iter().filter(|&&score| score == top).count() == 1 {
+            self.phase = MultiplayerPhase::GameOver(scores);
+        } else {
+            for score in scores.iter_mut().filter(|score| **score >= rules.points_to_win) {
+                *score = rules.sudden_death_points();
+            }
+            self.set_scores(&scores);
+            events.push(MultiplayerEvent::SuddenDeathReset { points: rules.sudden_death_points() });
+        }
+    }
+
+    fn set_scores(&mut self, scores: &[u8]) {
+        match self.phase {
+            MultiplayerPhase::CharacterChoosing(ref mut players) => {
+                for (player, &score) in players.iter_mut().zip(scores) {
+                    player.points = score;
+                }
+            },
+            MultiplayerPhase::BoosterChoosing(ref mut players) => {
+                for (player, &score) in players.iter_mut().zip(scores) {
+                    player.points = score;
+                }
+            },
+            MultiplayerPhase::MoveChoosing(ref mut players) => {
+                for (player, &score) in players.iter_mut().zip(scores) {
+                    player.points = score;
+                }
+            },
+            MultiplayerPhase::GameOver(ref mut points) => points.copy_from_slice(scores),
+        }
+    }
+}
+
+/// Awards headstarts and moves on to boosters once everyone has a character.
+fn resolve_characters(players: &mut [CharacterlessPlayer], events: &mut Vec<MultiplayerEvent>) -> Option<MultiplayerPhase> {
+    let characters: Vec<Character> = players.iter().map(|p| p.selected_character).collect::<Option<_>>()?;
+
+    if characters.iter().all(|&c| c == characters[0]) {
+        for player in players.iter_mut() {
+            player.selected_character = None;
+            player.character_streak.add(characters[0]);
+        }
+        events.push(MultiplayerEvent::CharactersTied(characters[0]));
+
+        return None;
+    }
+
+    let mut next = vec![];
+    for (index, player) in players.iter_mut().enumerate() {
+        let points: u8 = characters.iter()
+            .map(|&other| outcomes::get_headstart(characters[index], other).0)
+            .sum();
+
+        if points > 0 {
+            player.points += points;
+            events.push(MultiplayerEvent::HeadstartAwarded { player: index, points });
+        }
+        next.push(player.to_boosterless_player(characters[index]));
+    }
+
+    Some(MultiplayerPhase::BoosterChoosing(next))
+}
+
+/// Scores the moves once everyone has one.
+fn resolve_moves(players: &mut [MovelessPlayer], events: &mut Vec<MultiplayerEvent>) {
+    let moves: Vec<Move> = match players.iter().map(|p| p.selected_move).collect::<Option<_>>() {
+        Some(moves) => moves,
+        None => return,
+    };
+
+    let points = outcomes::get_points(players.iter().zip(&moves).map(|(p, &m)| (p.booster, m)).collect());
+    for ((player, &selected_move), &points) in players.iter_mut().zip(&moves).zip(&points) {
+        player.points += points;
+        player.move_streak.add(selected_move);
+        player.selected_move = None;
+    }
+    events.push(MultiplayerEvent::MovesRevealed { moves: moves.clone(), points });
+
+    for (index, player) in players.iter_mut().enumerate() {
+        let selected_move = moves[index];
+        let is_destroyed = moves.iter().enumerate()
+            .any(|(other, other_move)| other != index && DESTRUCTIVE_MOVES.contains(other_move));
+
+        if SINGLE_USE_MOVES.contains(&selected_move) {
+            player.destroyed_moves.push(selected_move);
+            events.push(MultiplayerEvent::SingleUseConsumed { player: index, consumed_move: selected_move });
+        } else if is_destroyed {
+            player.destroyed_moves.push(selected_move);
+            events.push(MultiplayerEvent::MoveDestroyed { player: index, destroyed_move: selected_move });
+        }
+    }
+}
+
+impl fmt::Debug for MultiplayerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MultiplayerEvent::CharacterSelected { player, character } => debug_struct(f, "CharacterSelected", &[
+                ("player", &player), ("character", &DisplayAsDebug(character)),
+            ]),
+            MultiplayerEvent::CharactersTied(character) => f.debug_tuple("CharactersTied").field(&DisplayAsDebug(character)).finish(),
+            MultiplayerEvent::HeadstartAwarded { player, points } => debug_struct(f, "HeadstartAwarded", &[
+                ("player", &player), ("points", &points),
+            ]),
+            MultiplayerEvent::BoosterSelected { player, booster } => debug_struct(f, "BoosterSelected", &[
+                ("player", &player), ("booster", &DisplayAsDebug(booster)),
+            ]),
+            MultiplayerEvent::MoveSelected { player, selected_move } => debug_struct(f, "MoveSelected", &[
+                ("player", &player), ("selected_move", &selected_move),
+            ]),
+            MultiplayerEvent::MovesRevealed { ref moves, ref points } => debug_struct(f, "MovesRevealed", &[
+                ("moves", moves), ("points", points),
+            ]),
+            MultiplayerEvent::MoveDestroyed { player, destroyed_move } => debug_struct(f, "MoveDestroyed", &[
+                ("player", &player), ("destroyed_move", &destroyed_move),
+            ]),
+            MultiplayerEvent::SingleUseConsumed { player, consumed_move } => debug_struct(f, "SingleUseConsumed", &[
+                ("player", &player), ("consumed_move", &consumed_move),
+            ]),
+            MultiplayerEvent::PenaltyApplied { player, penalty } => debug_struct(f, "PenaltyApplied", &[
+                ("player", &player), ("penalty", &penalty),
+            ]),
+            MultiplayerEvent::SuddenDeathReset { points } => debug_struct(f, "SuddenDeathReset", &[("points", &points)]),
+            MultiplayerEvent::GameOver { ref points } => debug_struct(f, "GameOver", &[("points", points)]),
+        }
+    }
+}
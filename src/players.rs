@@ -4,45 +4,71 @@ use nzsc_core::{
     moves::Move,
     streaks::{ CharacterStreak, MoveStreak, },
 };
+use super::rules::RuleSet;
+use super::two_player_game::PenaltyReason;
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize, };
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CharacterlessPlayer {
     pub points: u8,
     pub waits: u8,
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::CharacterStreakDef"))]
     pub character_streak: CharacterStreak,
+    /// Characters this player may not pick at all, such as the one they won
+    /// the last game of a `Series` with.
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::vec_character"))]
+    pub banned_characters: Vec<Character>,
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::option_character"))]
     pub selected_character: Option<Character>
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BoosterlessPlayer {
     pub points: u8,
     pub waits: u8,
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::CharacterDef"))]
     pub character: Character,
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::option_booster"))]
     pub selected_booster: Option<Booster>,
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MovelessPlayer {
     pub points: u8,
     pub waits: u8,
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::CharacterDef"))]
     pub character: Character,
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::BoosterDef"))]
     pub booster: Booster,
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::MoveStreakDef"))]
     pub move_streak: MoveStreak,
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::vec_move"))]
     pub destroyed_moves: Vec<Move>,
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::option_move"))]
     pub selected_move: Option<Move>,
 }
 
 impl CharacterlessPlayer {
     pub fn new() -> Self {
+        Self::with_rules(&RuleSet::default())
+    }
+
+    pub fn with_rules(rules: &RuleSet) -> Self {
         Self {
             points: 0,
-            waits: 4,
+            waits: rules.starting_waits,
             character_streak: CharacterStreak::new(),
+            banned_characters: vec![],
             selected_character: None,
         }
     }
 
-    pub fn available_characters(&self) -> Vec<Character> {
+    /// Characters that can be chosen under `rules` without a penalty.
+    pub fn available_characters(&self, rules: &RuleSet) -> Vec<Character> {
         let mut characters = vec![
             Character::Ninja,
             Character::Zombie,
@@ -50,13 +76,27 @@ impl CharacterlessPlayer {
             Character::Clown,
         ];
 
-        if self.character_streak.times == 3 {
+        if self.character_streak.times >= rules.streak_limit {
             characters.retain(|&c| Some(c) != self.character_streak.repeated_character);
         }
+        characters.retain(|c| !self.banned_characters.contains(c));
 
         characters
     }
 
+    /// Why choosing `character` would be penalized, if it would be.
+    pub(crate) fn character_penalty(&self, character: Character, rules: &RuleSet) -> Option<PenaltyReason> {
+        if self.banned_characters.contains(&character) {
+            Some(PenaltyReason::BannedCharacter)
+        } else if self.character_streak.times >= rules.streak_limit
+            && self.character_streak.repeated_character == Some(character)
+        {
+            Some(PenaltyReason::ExhaustedCharacter)
+        } else {
+            None
+        }
+    }
+
     pub fn penalize(&mut self, waits: u8) -> u8 {
         if self.waits < waits {
             self.waits = 0;
@@ -77,11 +117,26 @@ impl CharacterlessPlayer {
     }
 }
 
+impl Default for CharacterlessPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BoosterlessPlayer {
     pub fn available_boosters(&self) -> Vec<Booster> {
         self.character.get_boosters()
     }
 
+    /// Why choosing `booster` would be penalized, if it would be.
+    pub(crate) fn booster_penalty(&self, booster: Booster) -> Option<PenaltyReason> {
+        if self.available_boosters().contains(&booster) {
+            None
+        } else {
+            Some(PenaltyReason::WrongCharacterBooster)
+        }
+    }
+
     pub fn penalize(&mut self, waits: u8) -> u8 {
         if self.waits < waits {
             self.waits = 0;
@@ -106,7 +161,8 @@ impl BoosterlessPlayer {
 }
 
 impl MovelessPlayer {
-    pub fn available_moves(&self) -> Vec<Move> {
+    /// Moves that can be chosen under `rules` without a penalty.
+    pub fn available_moves(&self, rules: &RuleSet) -> Vec<Move> {
         let character_moves = self.character.get_moves();
         let booster_moves = self.booster.get_moves();
 
@@ -118,7 +174,7 @@ impl MovelessPlayer {
         available_moves.retain(|&a| !destroyed_moves.contains(&a));
 
         if let Some(streak_move) = self.move_streak.repeated_move {
-            if self.move_streak.times >= 3 {
+            if self.move_streak.times >= rules.streak_limit {
                 available_moves.retain(|&a| a != streak_move);
             }
         }
@@ -126,6 +182,30 @@ impl MovelessPlayer {
         available_moves
     }
 
+    /// Why choosing `a_move` would be penalized, if it would be.
+    pub(crate) fn move_penalty(&self, a_move: Move, rules: &RuleSet) -> Option<PenaltyReason> {
+        if self.available_moves(rules).contains(&a_move) {
+            None
+        } else if self.destroyed_moves.contains(&a_move) {
+            Some(PenaltyReason::DestroyedMove)
+        } else if self.move_streak.times >= rules.streak_limit
+            && self.move_streak.repeated_move == Some(a_move)
+        {
+            Some(PenaltyReason::ExhaustedMove)
+        } else {
+            let mut booster_moves = vec![];
+            for booster in &self.character.get_boosters() {
+                booster_moves.extend(booster.get_moves());
+            }
+
+            if booster_moves.contains(&a_move) {
+                Some(PenaltyReason::OtherBoosterMove)
+            } else {
+                Some(PenaltyReason::UnavailableMove)
+            }
+        }
+    }
+
     pub fn penalize(&mut self, waits: u8) -> u8 {
         if self.waits < waits {
             self.waits = 0;
@@ -0,0 +1,67 @@
+use super::two_player_game::PenaltyReason;
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize, };
+
+/// The numbers a game is played with.
+///
+/// `RuleSet::default()` is standard NZSC.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RuleSet {
+    /// Points needed to win.
+    pub points_to_win: u8,
+    /// Waits each player starts with.
+    pub starting_waits: u8,
+    /// How many times in a row a character or move can be chosen.
+    pub streak_limit: u8,
+    pub unparseable_penalty: u8,
+    pub exhausted_character_penalty: u8,
+    pub banned_character_penalty: u8,
+    pub wrong_character_booster_penalty: u8,
+    pub destroyed_move_penalty: u8,
+    pub exhausted_move_penalty: u8,
+    pub other_booster_move_penalty: u8,
+    pub unavailable_move_penalty: u8,
+    pub timeout_penalty: u8,
+}
+
+impl RuleSet {
+    /// How many waits a choice penalized for `reason` costs.
+    pub fn penalty(&self, reason: PenaltyReason) -> u8 {
+        match reason {
+            PenaltyReason::Unparseable => self.unparseable_penalty,
+            PenaltyReason::ExhaustedCharacter => self.exhausted_character_penalty,
+            PenaltyReason::BannedCharacter => self.banned_character_penalty,
+            PenaltyReason::WrongCharacterBooster => self.wrong_character_booster_penalty,
+            PenaltyReason::DestroyedMove => self.destroyed_move_penalty,
+            PenaltyReason::ExhaustedMove => self.exhausted_move_penalty,
+            PenaltyReason::OtherBoosterMove => self.other_booster_move_penalty,
+            PenaltyReason::UnavailableMove => self.unavailable_move_penalty,
+            PenaltyReason::TimedOut => self.timeout_penalty,
+        }
+    }
+
+    /// The score both players are set back to when they reach `points_to_win` together.
+    pub fn sudden_death_points(&self) -> u8 {
+        self.points_to_win.saturating_sub(1)
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            points_to_win: 5,
+            starting_waits: 4,
+            streak_limit: 3,
+            unparseable_penalty: 4,
+            exhausted_character_penalty: 3,
+            banned_character_penalty: 3,
+            wrong_character_booster_penalty: 3,
+            destroyed_move_penalty: 4,
+            exhausted_move_penalty: 3,
+            other_booster_move_penalty: 2,
+            unavailable_move_penalty: 3,
+            timeout_penalty: 4,
+        }
+    }
+}
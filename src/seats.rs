@@ -0,0 +1,91 @@
+//! Choice handling shared by every game mode: parsing a choice for the
+//! phase it was made in, deciding whether it is rejected, penalized or
+//! legal, and moving on to moves once everyone has a booster.
+
+use super::choices::Choice;
+use super::players::{
+    CharacterlessPlayer,
+    BoosterlessPlayer,
+    MovelessPlayer,
+};
+use super::rules::RuleSet;
+use super::two_player_game::{
+    ChoiceError,
+    PenaltyReason,
+};
+use nzsc_core::{
+    characters::Character,
+    boosters::Booster,
+    moves::Move,
+};
+use std::str::FromStr;
+
+/// One player, as far as the current phase is concerned.
+pub(crate) enum Seat<'a> {
+    CharacterChoosing(&'a CharacterlessPlayer),
+    BoosterChoosing(&'a BoosterlessPlayer),
+    MoveChoosing(&'a MovelessPlayer),
+    GameOver,
+}
+
+impl<'a> Seat<'a> {
+    /// Whether the player still has to choose this phase.
+    pub(crate) fn is_waiting(&self) -> bool {
+        match *self {
+            Seat::CharacterChoosing(player) => player.selected_character.is_none(),
+            Seat::BoosterChoosing(player) => player.selected_booster.is_none(),
+            Seat::MoveChoosing(player) => player.selected_move.is_none(),
+            Seat::GameOver => false,
+        }
+    }
+
+    /// Reads `choice` as whatever the phase asks for.
+    pub(crate) fn parse_choice(&self, choice: &str) -> Option<Choice> {
+        match *self {
+            Seat::CharacterChoosing(_) => Character::from_str(choice).ok().map(Choice::Character),
+            Seat::BoosterChoosing(_) => Booster::from_str(choice).ok().map(Choice::Booster),
+            Seat::MoveChoosing(_) => Move::from_str(choice).ok().map(Choice::Move),
+            Seat::GameOver => None,
+        }
+    }
+
+    /// Decides whether `choice` is rejected, penalized, or legal.
+    ///
+    /// `None` stands for a choice that could not be parsed.
+    pub(crate) fn classify_choice(&self, choice: Option<Choice>, rules: &RuleSet) -> Result<Option<PenaltyReason>, ChoiceError> {
+        if let Seat::GameOver = *self {
+            return Err(ChoiceError::GameOver);
+        }
+        if !self.is_waiting() {
+            // Cannot repick.
+            return Err(ChoiceError::AlreadyChosen);
+        }
+        let choice = match choice {
+            Some(choice) => choice,
+            None => return Ok(Some(PenaltyReason::Unparseable)),
+        };
+
+        match *self {
+            Seat::CharacterChoosing(player) => {
+                let character = choice.character().ok_or(ChoiceError::WrongPhase)?;
+                Ok(player.character_penalty(character, rules))
+            },
+            Seat::BoosterChoosing(player) => {
+                let booster = choice.booster().ok_or(ChoiceError::WrongPhase)?;
+                Ok(player.booster_penalty(booster))
+            },
+            Seat::MoveChoosing(player) => {
+                let a_move = choice.move_().ok_or(ChoiceError::WrongPhase)?;
+                Ok(player.move_penalty(a_move, rules))
+            },
+            Seat::GameOver => unreachable!(),
+        }
+    }
+}
+
+/// Everyone's move-phase players, in seating order, once everyone has a booster.
+pub(crate) fn resolve_boosters(players: &[BoosterlessPlayer]) -> Option<Vec<MovelessPlayer>> {
+    let boosters: Vec<Booster> = players.iter().map(|p| p.selected_booster).collect::<Option<_>>()?;
+
+    Some(players.iter().zip(boosters).map(|(player, booster)| player.to_moveless_player(booster)).collect())
+}
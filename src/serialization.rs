@@ -0,0 +1,136 @@
+//! Serde definitions for the `nzsc_core` types the game state is built from.
+
+use nzsc_core::{
+    characters::Character,
+    boosters::Booster,
+    moves::Move,
+    streaks::{ CharacterStreak, MoveStreak, },
+};
+use serde::{ Serialize, Deserialize, };
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Character")]
+pub enum CharacterDef {
+    Ninja,
+    Zombie,
+    Samurai,
+    Clown,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Booster")]
+pub enum BoosterDef {
+    Shadow,
+    Speedy,
+    Regenerative,
+    ZombieCorps,
+    Atlas,
+    Strong,
+    Backwards,
+    Moustachio,
+    None,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Move")]
+pub enum MoveDef {
+    Kick,
+    NinjaSword,
+    Nunchucks,
+    ShadowFireball,
+    ShadowSlip,
+    RunInCircles,
+    LightningFastKarateChop,
+    Rampage,
+    Muscle,
+    Zap,
+    Regenerate,
+    Gravedigger,
+    ZombieCorps,
+    Apocalypse,
+    SamuraiSword,
+    Helmet,
+    Smash,
+    Lightning,
+    Earthquake,
+    Twist,
+    Bend,
+    JugglingKnives,
+    AcidSpray,
+    Nose,
+    BackwardsMoustachio,
+    NoseOfTheTaunted,
+    MustacheMash,
+    BigHairyDeal,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "CharacterStreak")]
+pub struct CharacterStreakDef {
+    #[serde(with = "option_character")]
+    pub repeated_character: Option<Character>,
+    pub times: u8,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "MoveStreak")]
+pub struct MoveStreakDef {
+    #[serde(with = "option_move")]
+    pub repeated_move: Option<Move>,
+    pub times: u8,
+}
+
+macro_rules! remote_option {
+    ($module:ident, $remote:ty, $def:tt) => {
+        pub mod $module {
+            use super::*;
+            use serde::{ Serializer, Deserializer, };
+
+            #[derive(Serialize)]
+            struct Wrapper<'a>(#[serde(with = $def)] &'a $remote);
+
+            #[derive(Deserialize)]
+            struct OwnedWrapper(#[serde(with = $def)] $remote);
+
+            pub fn serialize<S: Serializer>(value: &Option<$remote>, serializer: S) -> Result<S::Ok, S::Error> {
+                value.as_ref().map(Wrapper).serialize(serializer)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<$remote>, D::Error> {
+                Option::<OwnedWrapper>::deserialize(deserializer).map(|value| value.map(|OwnedWrapper(value)| value))
+            }
+        }
+    };
+}
+
+remote_option!(option_character, Character, "CharacterDef");
+remote_option!(option_booster, Booster, "BoosterDef");
+remote_option!(option_move, Move, "MoveDef");
+
+macro_rules! remote_vec {
+    ($module:ident, $remote:ty, $def:tt) => {
+        pub mod $module {
+            use super::*;
+            use serde::{ Serializer, Deserializer, };
+
+            #[derive(Serialize)]
+            struct Wrapper<'a>(#[serde(with = $def)] &'a $remote);
+
+            #[derive(Deserialize)]
+            struct OwnedWrapper(#[serde(with = $def)] $remote);
+
+            pub fn serialize<S: Serializer>(values: &[$remote], serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(values.iter().map(Wrapper))
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<$remote>, D::Error> {
+                Vec::<OwnedWrapper>::deserialize(deserializer).map(|values| {
+                    values.into_iter().map(|OwnedWrapper(value)| value).collect()
+                })
+            }
+        }
+    };
+}
+
+remote_vec!(vec_character, Character, "CharacterDef");
+remote_vec!(vec_move, Move, "MoveDef");
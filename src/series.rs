@@ -0,0 +1,282 @@
+//! Best-of-N matches.
+//!
+//! A `Series` has the same choice and view methods as `NZSCTwoPlayerGame`
+//! and forwards them to the current game. When a game ends, the next one
+//! starts straight away. Drawn games count toward `best_of`, so a series
+//! never has more than `best_of` games. It ends early once one player has
+//! more wins than the other could catch up on in the games left.
+//! The `Event::GameOver` in a choice's outcome is how clients can tell a
+//! game ended, since the view that follows is already of the next game.
+
+use super::choices::Choice;
+use super::events::Event;
+use super::history::HistoryEntry;
+use super::rules::RuleSet;
+use super::two_player_game::{
+    ChoiceError,
+    ChoiceOutcome,
+    NZSCTwoPlayerGame,
+    Penalty,
+    Phase,
+    WhichPlayer,
+    seat_index,
+};
+use super::views::{
+    PlayerView,
+    SpectatorView,
+};
+use nzsc_core::characters::Character;
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize, };
+
+/// What one game of a series passes on to the next.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CarryOver {
+    /// Every game starts fresh.
+    Nothing,
+    /// The winner of a game cannot pick the character they won with in the
+    /// next one. See `NZSCTwoPlayerGame::with_bans`.
+    BanWinnersCharacter,
+}
+
+/// What one player is allowed to see of a series: their view of the
+/// current game, and the score so far.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SeriesView {
+    pub game: PlayerView,
+    pub a_wins: u8,
+    pub b_wins: u8,
+    /// Which game of the series `game` is, counting from 1.
+    pub game_number: u8,
+    pub best_of: u8,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Series {
+    game: NZSCTwoPlayerGame,
+    finished_games: Vec<NZSCTwoPlayerGame>,
+    rules: RuleSet,
+    best_of: u8,
+    carry_over: CarryOver,
+    /// Games won by A, then B.
+    wins: [u8; 2],
+}
+
+impl Series {
+    /// # Panics
+    ///
+    /// Panics if `best_of` is 0.
+    pub fn new(best_of: u8) -> Self {
+        Self::with_rules(best_of, RuleSet::default(), CarryOver::Nothing)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `best_of` is 0.
+    pub fn with_rules(best_of: u8, rules: RuleSet, carry_over: CarryOver) -> Self {
+        assert!(best_of > 0, "A series needs at least one game");
+
+        Self {
+            game: NZSCTwoPlayerGame::with_rules(rules),
+            finished_games: vec![],
+            rules,
+            best_of,
+            carry_over,
+            wins: [0, 0],
+        }
+    }
+
+    /// The game being played, or the last one once the series is over.
+    pub fn game(&self) -> &NZSCTwoPlayerGame {
+        &self.game
+    }
+
+    /// Every game that has ended, oldest first. Once the series is over,
+    /// this includes the last game.
+    pub fn finished_games(&self) -> &[NZSCTwoPlayerGame] {
+        &self.finished_games
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn best_of(&self) -> u8 {
+        self.best_of
+    }
+
+    pub fn carry_over(&self) -> CarryOver {
+        self.carry_over
+    }
+
+    /// Wins that take the series however the other games go.
+    pub fn games_to_win(&self) -> u8 {
+        self.best_of / 2 + 1
+    }
+
+    /// Games `player` has won so far. Drawn games count for neither player.
+    pub fn wins(&self, player: WhichPlayer) -> u8 {
+        self.wins[seat_index(player)]
+    }
+
+    /// Whether the series is decided: either all `best_of` games have been
+    /// played, or the trailing player could not catch up in the games left.
+    pub fn is_over(&self) -> bool {
+        let played = self.finished_games.len() as u8;
+        let lead = self.wins[0].abs_diff(self.wins[1]);
+
+        played >= self.best_of || lead > self.best_of - played
+    }
+
+    /// Who won the series, or `None` if it is still going or ended with
+    /// both players on the same number of wins.
+    pub fn winner(&self) -> Option<WhichPlayer> {
+        let [a_wins, b_wins] = self.wins;
+
+        if !self.is_over() || a_wins == b_wins {
+            None
+        } else if a_wins > b_wins {
+            Some(WhichPlayer::PlayerA)
+        } else {
+            Some(WhichPlayer::PlayerB)
+        }
+    }
+
+    /// Like `NZSCTwoPlayerGame::history`, for the current game. Earlier
+    /// games' histories are kept in `finished_games`.
+    pub fn history(&self) -> &[HistoryEntry] {
+        self.game.history()
+    }
+
+    /// Like `NZSCTwoPlayerGame::is_waiting_on`, for the current game.
+    pub fn is_waiting_on(&self, player: WhichPlayer) -> bool {
+        self.game.is_waiting_on(player)
+    }
+
+    /// Which game of the series is being played, counting from 1. Once the
+    /// series is over, this is the last game's number.
+    pub fn game_number(&self) -> u8 {
+        let finished = self.finished_games.len() as u8;
+
+        if self.is_over() { finished } else { finished + 1 }
+    }
+
+    /// Like `NZSCTwoPlayerGame::view_for`, for the current game, with the series score.
+    pub fn view_for(&self, player: WhichPlayer) -> SeriesView {
+        SeriesView {
+            game: self.game.view_for(player),
+            a_wins: self.wins[0],
+            b_wins: self.wins[1],
+            game_number: self.game_number(),
+            best_of: self.best_of,
+        }
+    }
+
+    /// Like `NZSCTwoPlayerGame::spectator_view`, for the current game.
+    pub fn spectator_view(&self) -> SpectatorView {
+        self.game.spectator_view()
+    }
+
+    /// Like `NZSCTwoPlayerGame::legal_choices`, for the current game.
+    pub fn legal_choices(&self, player: WhichPlayer) -> Vec<Choice> {
+        self.game.legal_choices(player)
+    }
+
+    /// Like `NZSCTwoPlayerGame::evaluate_choice`, for the current game.
+    pub fn evaluate_choice(&self, chooser: WhichPlayer, choice: &str) -> Result<Option<Penalty>, ChoiceError> {
+        self.game.evaluate_choice(chooser, choice)
+    }
+
+    pub fn process_choice(&mut self, chooser: WhichPlayer, choice: String) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.process_choice(chooser, choice)?;
+        self.advance();
+
+        Ok(outcome)
+    }
+
+    pub fn choose(&mut self, chooser: WhichPlayer, choice: Choice) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.choose(chooser, choice)?;
+        self.advance();
+
+        Ok(outcome)
+    }
+
+    pub fn time_out(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.time_out(player)?;
+        self.advance();
+
+        Ok(outcome)
+    }
+
+    /// Gives up the current game, not the whole series.
+    pub fn resign(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.resign(player)?;
+        self.advance();
+
+        Ok(outcome)
+    }
+
+    /// Ends the current game against `player`, not the whole series.
+    pub fn forfeit(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.forfeit(player)?;
+        self.advance();
+
+        Ok(outcome)
+    }
+
+    /// Offers to draw the current game.
+    pub fn offer_draw(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.offer_draw(player)?;
+        self.advance();
+
+        Ok(outcome)
+    }
+
+    pub fn accept_draw(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.accept_draw(player)?;
+        self.advance();
+
+        Ok(outcome)
+    }
+
+    /// Records the current game once it is over, and starts the next one
+    /// unless the series is decided.
+    fn advance(&mut self) {
+        if let Phase::GameOver(_, _) = self.game.phase {} else {
+            return;
+        }
+
+        let winner = self.game.winner();
+        if let Some(winner) = winner {
+            self.wins[seat_index(winner)] += 1;
+        }
+        self.finished_games.push(self.game.clone());
+
+        if self.is_over() {
+            return;
+        }
+
+        let mut banned_characters = [vec![], vec![]];
+        if self.carry_over == CarryOver::BanWinnersCharacter {
+            if let Some(winner) = winner {
+                banned_characters[seat_index(winner)].extend(last_character(&self.game, winner));
+            }
+        }
+
+        self.game = NZSCTwoPlayerGame::with_bans(self.rules, banned_characters);
+    }
+}
+
+/// The character `player` last picked in `game`.
+fn last_character(game: &NZSCTwoPlayerGame, player: WhichPlayer) -> Option<Character> {
+    game.history().iter().rev()
+        .flat_map(|entry| entry.events.iter().rev())
+        .filter_map(|event| match *event {
+            Event::CharacterSelected { player: chooser, character } if chooser == player => Some(character),
+            _ => None,
+        })
+        .next()
+}
@@ -0,0 +1,419 @@
+//! Hosting many matches over TCP.
+//!
+//! # Protocol
+//!
+//! Clients and the server exchange JSON objects, one per line. Each object
+//! has a `"type"` field naming the message; the other fields are listed below.
+//!
+//! Client to server:
+//!
+//! - `{"type": "Create"}` opens a room with the sender as player A.
+//! - `{"type": "Join", "code": "..."}` takes the free seat in a room whose
+//!   game has not started. A player whose connection closes mid-game
+//!   forfeits it, and nobody can take their seat.
+//! - `{"type": "Queue"}` waits for a random opponent. Whoever was waiting
+//!   first becomes player A.
+//! - `{"type": "Spectate", "code": "..."}` watches a room.
+//! - `{"type": "Choose", "choice": "..."}` makes a choice, exactly like
+//!   `NZSCTwoPlayerGame::process_choice`. Choices, resignations and draw
+//!   offers are refused until both players have joined.
+//! - `{"type": "Resign"}` gives up, ending the game in the opponent's favor.
+//! - `{"type": "OfferDraw"}` offers the opponent a draw, which stands until
+//!   they accept it or choose. `{"type": "AcceptDraw"}` accepts their offer.
+//! - `{"type": "Rematch"}` asks for a new game once the current one is over.
+//!   It starts once both players have asked, and is refused once either
+//!   has left the room.
+//! - `{"type": "State"}` asks for a fresh `View` or `SpectatorView`.
+//! - `{"type": "Leave"}` leaves the queue or room, like disconnecting.
+//!
+//! Server to client:
+//!
+//! - `{"type": "Queued"}` confirms the sender is waiting for an opponent.
+//! - `{"type": "Joined", "code": "...", "player": "PlayerA"}` tells a player
+//!   which room and seat they have, after `Create`, `Join` or matchmaking.
+//! - `{"type": "Spectating", "code": "..."}` confirms a `Spectate`.
+//! - `{"type": "Outcome", "outcome": {...}}` answers an accepted `Choose` with
+//!   its `ChoiceOutcome`. Only the chooser receives it.
+//! - `{"type": "RematchRequested", "player": "PlayerB"}` is sent to the room
+//!   when one player asks for a rematch.
+//! - `{"type": "View", "view": {...}}` is a player's `PlayerView`, and
+//!   `{"type": "SpectatorView", "view": {...}}` a spectator's `SpectatorView`.
+//!   One is pushed to everyone in a room whenever it changes, and sent in
+//!   reply to `State`. Views include any pending draw offer and, once the
+//!   game is over, how it ended.
+//! - `{"type": "RoomClosed", "code": "..."}` tells everyone left in a room
+//!   that it was closed for being idle too long.
+//! - `{"type": "Error", "message": "..."}` reports a message that could not
+//!   be parsed or acted on. The connection stays open.
+//!
+//! Each connection's messages are written by a thread of its own, so a
+//! client that stops reading cannot hold up anyone else. A client that
+//! falls more than `OUTBOX_CAPACITY` messages behind is disconnected.
+
+use super::lobby::{
+    ClientId,
+    Lobby,
+    Membership,
+};
+use super::rules::RuleSet;
+use super::two_player_game::{
+    ChoiceOutcome,
+    WhichPlayer,
+};
+use super::views::{
+    PlayerView,
+    SpectatorView,
+};
+use serde::{ Serialize, Deserialize, };
+use serde_json;
+use std::collections::HashMap;
+use std::io::{ self, BufRead, BufReader, Write, };
+use std::net::{ Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, };
+use std::sync::{ Arc, Mutex, };
+use std::sync::mpsc::{ self, SyncSender, TrySendError, };
+use std::thread;
+use std::time::{ Duration, Instant, SystemTime, UNIX_EPOCH, };
+
+#[derive(Clone, Debug)]
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClientMessage {
+    Create,
+    Join { code: String },
+    Queue,
+    Spectate { code: String },
+    Choose { choice: String },
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    Rematch,
+    State,
+    Leave,
+}
+
+#[derive(Clone)]
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ServerMessage {
+    Queued,
+    Joined { code: String, player: WhichPlayer },
+    Spectating { code: String },
+    Outcome { outcome: ChoiceOutcome },
+    RematchRequested { player: WhichPlayer },
+    View { view: PlayerView },
+    SpectatorView { view: SpectatorView },
+    RoomClosed { code: String },
+    Error { message: String },
+}
+
+/// Accepts connections and runs every room in its lobby.
+pub struct Server {
+    listener: TcpListener,
+    state: Arc<Mutex<State>>,
+}
+
+/// How many messages can wait to be written to one client.
+pub const OUTBOX_CAPACITY: usize = 256;
+
+struct State {
+    lobby: Lobby,
+    connections: HashMap<ClientId, Connection>,
+    next_client: ClientId,
+}
+
+/// A client's connection, as seen while holding the server lock.
+struct Connection {
+    /// Lines for the connection's writer thread.
+    outbox: SyncSender<String>,
+    /// Kept to disconnect a client that falls too far behind.
+    stream: TcpStream,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        Self::bind_with_rules(address, RuleSet::default())
+    }
+
+    /// Like `bind`, with every game played under `rules`.
+    pub fn bind_with_rules<A: ToSocketAddrs>(address: A, rules: RuleSet) -> io::Result<Self> {
+        // Room codes only need to differ between runs, not be unpredictable.
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64);
+        let mut lobby = Lobby::new(seed);
+        lobby.rules = rules;
+
+        Self::bind_with_lobby(address, lobby)
+    }
+
+    /// Like `bind`, serving `lobby` and its settings.
+    pub fn bind_with_lobby<A: ToSocketAddrs>(address: A, lobby: Lobby) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            state: Arc::new(Mutex::new(State {
+                lobby,
+                connections: HashMap::new(),
+                next_client: 0,
+            })),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serves connections forever, one thread each, closing idle rooms in the background.
+    pub fn run(self) -> io::Result<()> {
+        let reaper_state = self.state.clone();
+        thread::spawn(move || close_idle_rooms(&reaper_state));
+
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let state = self.state.clone();
+
+            thread::spawn(move || {
+                let _ = handle_connection(stream, &state);
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Connection {
+    /// Queues `message` without waiting for it to be written.
+    fn send(&self, message: &ServerMessage) {
+        let mut line = serde_json::to_string(message).expect("server messages serialize");
+        line.push('\n');
+
+        if let Err(TrySendError::Full(_)) = self.outbox.try_send(line) {
+            // Also unblocks the writer thread and ends the reading one.
+            let _ = self.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl State {
+    fn send(&self, client: ClientId, message: &ServerMessage) {
+        if let Some(connection) = self.connections.get(&client) {
+            connection.send(message);
+        }
+    }
+
+    /// Sends everyone in the room with `code` their view of it.
+    fn push_room(&self, code: &str) {
+        let room = match self.lobby.room(code) {
+            Some(room) => room,
+            None => return,
+        };
+
+        for (&client, &player) in room.players.iter().zip(&[WhichPlayer::PlayerA, WhichPlayer::PlayerB]) {
+            if let Some(client) = client {
+                self.send(client, &ServerMessage::View { view: room.game.view_for(player) });
+            }
+        }
+
+        for &spectator in &room.spectators {
+            self.send(spectator, &ServerMessage::SpectatorView { view: room.game.spectator_view() });
+        }
+    }
+
+    /// The code of the room `client` is in, if any.
+    fn room_code(&self, client: ClientId) -> Option<String> {
+        self.lobby.room_of(client).map(|room| room.code.clone())
+    }
+
+    fn leave(&mut self, client: ClientId) {
+        let code = self.room_code(client);
+        self.lobby.leave(client);
+
+        if let Some(code) = code {
+            self.push_room(&code);
+        }
+    }
+
+    fn handle(&mut self, client: ClientId, message: ClientMessage) {
+        let now = Instant::now();
+
+        match message {
+            ClientMessage::Create => {
+                match self.lobby.create_room(client, now) {
+                    Ok(code) => {
+                        self.send(client, &ServerMessage::Joined { code: code.clone(), player: WhichPlayer::PlayerA });
+                        self.push_room(&code);
+                    },
+                    Err(lobby_error) => self.send(client, &error(&lobby_error)),
+                }
+            },
+            ClientMessage::Join { code } => {
+                match self.lobby.join_room(client, &code, now) {
+                    Ok(player) => {
+                        self.send(client, &ServerMessage::Joined { code: code.clone(), player });
+                        self.push_room(&code);
+                    },
+                    Err(lobby_error) => self.send(client, &error(&lobby_error)),
+                }
+            },
+            ClientMessage::Queue => {
+                match self.lobby.enqueue(client, now) {
+                    Ok(Some(code)) => {
+                        let players = self.lobby.room(&code).expect("room was just opened").players;
+                        for (&seated, &player) in players.iter().zip(&[WhichPlayer::PlayerA, WhichPlayer::PlayerB]) {
+                            if let Some(seated) = seated {
+                                self.send(seated, &ServerMessage::Joined { code: code.clone(), player });
+                            }
+                        }
+                        self.push_room(&code);
+                    },
+                    Ok(None) => self.send(client, &ServerMessage::Queued),
+                    Err(lobby_error) => self.send(client, &error(&lobby_error)),
+                }
+            },
+            ClientMessage::Spectate { code } => {
+                match self.lobby.spectate(client, &code, now) {
+                    Ok(()) => {
+                        self.send(client, &ServerMessage::Spectating { code: code.clone() });
+                        let view = self.lobby.room(&code).expect("spectated room exists").game.spectator_view();
+                        self.send(client, &ServerMessage::SpectatorView { view });
+                    },
+                    Err(lobby_error) => self.send(client, &error(&lobby_error)),
+                }
+            },
+            ClientMessage::Choose { choice } => {
+                match self.lobby.choose(client, choice, now) {
+                    Ok(outcome) => {
+                        self.send(client, &ServerMessage::Outcome { outcome });
+                        self.push_current_room(client);
+                    },
+                    Err(lobby_error) => self.send(client, &error(&lobby_error)),
+                }
+            },
+            ClientMessage::Resign | ClientMessage::OfferDraw | ClientMessage::AcceptDraw => {
+                let result = match message {
+                    ClientMessage::Resign => self.lobby.resign(client, now),
+                    ClientMessage::OfferDraw => self.lobby.offer_draw(client, now),
+                    _ => self.lobby.accept_draw(client, now),
+                };
+
+                match result {
+                    Ok(_) => self.push_current_room(client),
+                    Err(lobby_error) => self.send(client, &error(&lobby_error)),
+                }
+            },
+            ClientMessage::Rematch => {
+                match self.lobby.request_rematch(client, now) {
+                    Ok(true) => self.push_current_room(client),
+                    Ok(false) => {
+                        let room = self.lobby.room_of(client).expect("player is in a room");
+                        let player = match self.lobby.membership(client) {
+                            Some(&Membership::Player { player, .. }) => player,
+                            _ => unreachable!(),
+                        };
+                        for member in room.members() {
+                            self.send(member, &ServerMessage::RematchRequested { player });
+                        }
+                    },
+                    Err(lobby_error) => self.send(client, &error(&lobby_error)),
+                }
+            },
+            ClientMessage::State => {
+                let message = match (self.lobby.membership(client), self.lobby.room_of(client)) {
+                    (Some(&Membership::Player { player, .. }), Some(room)) => ServerMessage::View {
+                        view: room.game.view_for(player),
+                    },
+                    (Some(&Membership::Spectator { .. }), Some(room)) => ServerMessage::SpectatorView {
+                        view: room.game.spectator_view(),
+                    },
+                    _ => ServerMessage::Error {
+                        message: "Not in a room".to_string(),
+                    },
+                };
+                self.send(client, &message);
+            },
+            ClientMessage::Leave => self.leave(client),
+        }
+    }
+
+    fn push_current_room(&self, client: ClientId) {
+        if let Some(code) = self.room_code(client) {
+            self.push_room(&code);
+        }
+    }
+}
+
+fn error<E: ToString>(error: &E) -> ServerMessage {
+    ServerMessage::Error {
+        message: error.to_string(),
+    }
+}
+
+fn close_idle_rooms(state: &Mutex<State>) {
+    loop {
+        let interval = {
+            let mut state = state.lock().expect("server lock");
+            let closed = state.lobby.remove_idle_rooms(Instant::now());
+
+            for room in closed {
+                for member in room.members() {
+                    state.send(member, &ServerMessage::RoomClosed { code: room.code.clone() });
+                }
+            }
+
+            state.lobby.idle_timeout.clamp(Duration::from_millis(10), Duration::from_secs(1))
+        };
+
+        thread::sleep(interval);
+    }
+}
+
+fn handle_connection(stream: TcpStream, state: &Mutex<State>) -> io::Result<()> {
+    let (outbox, lines) = mpsc::sync_channel::<String>(OUTBOX_CAPACITY);
+    let mut writer = stream.try_clone()?;
+    // Ends once the connection is removed from the state, dropping `outbox`.
+    thread::spawn(move || {
+        for line in lines {
+            if writer.write_all(line.as_bytes()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let client = {
+        let mut state = state.lock().expect("server lock");
+        let client = state.next_client;
+        state.next_client += 1;
+        state.connections.insert(client, Connection {
+            outbox,
+            stream: stream.try_clone()?,
+        });
+
+        client
+    };
+
+    let result = serve(stream, state, client);
+
+    let mut state = state.lock().expect("server lock");
+    state.leave(client);
+    state.connections.remove(&client);
+
+    result
+}
+
+/// Handles messages from `client` until the connection closes.
+fn serve(stream: TcpStream, state: &Mutex<State>, client: ClientId) -> io::Result<()> {
+    let reader = BufReader::new(stream);
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut state = state.lock().expect("server lock");
+
+        match serde_json::from_str(&line) {
+            Ok(message) => state.handle(client, message),
+            Err(parse_error) => state.send(client, &error(&parse_error)),
+        }
+    }
+
+    Ok(())
+}
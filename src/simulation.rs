@@ -0,0 +1,172 @@
+//! Playing many bot-versus-bot games and summarizing them.
+
+use super::events::Event;
+use super::random::Rng;
+use super::rules::RuleSet;
+use super::strategies::Strategy;
+use super::two_player_game::{
+    NZSCTwoPlayerGame,
+    Phase,
+    PenaltyReason,
+    WhichPlayer,
+};
+use nzsc_core::{
+    characters::Character,
+    boosters::Booster,
+};
+
+/// Games still going after this many turns are counted as unfinished.
+pub const MAX_TURNS: u32 = 1000;
+
+/// Totals for one side of a simulation.
+#[derive(Clone, Default)]
+pub struct PlayerReport {
+    pub wins: u32,
+    /// How often each character was locked in, including picks that tied.
+    pub character_picks: Vec<(Character, u32)>,
+    pub booster_picks: Vec<(Booster, u32)>,
+    pub penalties: Vec<(PenaltyReason, u32)>,
+}
+
+/// The results of `simulate`.
+#[derive(Clone, Default)]
+pub struct SimulationReport {
+    pub games: u32,
+    pub a: PlayerReport,
+    pub b: PlayerReport,
+    pub draws: u32,
+    /// Games that hit `MAX_TURNS`, or where neither strategy would choose
+    /// although it had a legal choice.
+    pub unfinished: u32,
+    /// Total accepted choices across every game.
+    pub total_choices: u64,
+}
+
+impl PlayerReport {
+    pub fn win_rate(&self, games: u32) -> f64 {
+        if games == 0 {
+            0.0
+        } else {
+            self.wins as f64 / games as f64
+        }
+    }
+
+    pub fn penalty_count(&self) -> u32 {
+        self.penalties.iter().map(|&(_, count)| count).sum()
+    }
+}
+
+impl SimulationReport {
+    /// The average number of accepted choices per game.
+    pub fn average_game_length(&self) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.total_choices as f64 / self.games as f64
+        }
+    }
+
+    fn player_mut(&mut self, player: WhichPlayer) -> &mut PlayerReport {
+        match player {
+            WhichPlayer::PlayerA => &mut self.a,
+            WhichPlayer::PlayerB => &mut self.b,
+        }
+    }
+
+    fn record(&mut self, event: &Event) {
+        match *event {
+            Event::CharacterSelected { player, character } => {
+                increment(&mut self.player_mut(player).character_picks, character);
+            },
+            Event::BoosterSelected { player, booster } => {
+                increment(&mut self.player_mut(player).booster_picks, booster);
+            },
+            Event::PenaltyApplied { player, penalty } => {
+                increment(&mut self.player_mut(player).penalties, penalty.reason);
+            },
+            _ => {},
+        }
+    }
+}
+
+fn increment<T: PartialEq>(counts: &mut Vec<(T, u32)>, key: T) {
+    if let Some(entry) = counts.iter_mut().find(|entry| entry.0 == key) {
+        entry.1 += 1;
+        return;
+    }
+
+    counts.push((key, 1));
+}
+
+/// Plays `games` standard games between `a` (as player A) and `b` (as player B).
+///
+/// Each game reseeds both strategies from `seed`, so the same seed gives the same report.
+/// A player left without a legal choice makes `cheapest_penalized_choice` instead.
+pub fn simulate<A, B>(a: &mut A, b: &mut B, seed: u64, games: u32) -> SimulationReport
+    where A: Strategy + ?Sized,
+          B: Strategy + ?Sized,
+{
+    simulate_with_rules(RuleSet::default(), a, b, seed, games)
+}
+
+/// Like `simulate`, with every game played under `rules`.
+pub fn simulate_with_rules<A, B>(rules: RuleSet, a: &mut A, b: &mut B, seed: u64, games: u32) -> SimulationReport
+    where A: Strategy + ?Sized,
+          B: Strategy + ?Sized,
+{
+    let mut rng = Rng::new(seed);
+    let mut report = SimulationReport::default();
+
+    for _ in 0..games {
+        a.reseed(rng.next_u64());
+        b.reseed(rng.next_u64());
+
+        let mut game = NZSCTwoPlayerGame::with_rules(rules);
+        let mut finished = false;
+
+        for _ in 0..MAX_TURNS {
+            let mut anyone_chose = false;
+
+            for &player in &[WhichPlayer::PlayerA, WhichPlayer::PlayerB] {
+                let choice = match player {
+                    WhichPlayer::PlayerA => a.choose(&game.view_for(player)),
+                    WhichPlayer::PlayerB => b.choose(&game.view_for(player)),
+                };
+                let choice = choice.or_else(|| game.cheapest_penalized_choice(player));
+
+                if let Some(choice) = choice {
+                    if let Ok(outcome) = game.process_choice(player, choice.to_string()) {
+                        anyone_chose = true;
+                        report.total_choices += 1;
+
+                        for event in &outcome.events {
+                            report.record(event);
+                        }
+                    }
+                }
+            }
+
+            if let Phase::GameOver(_, _) = game.phase {
+                match game.winner() {
+                    Some(WhichPlayer::PlayerA) => report.a.wins += 1,
+                    Some(WhichPlayer::PlayerB) => report.b.wins += 1,
+                    None => report.draws += 1,
+                }
+
+                finished = true;
+                break;
+            }
+
+            if !anyone_chose {
+                break;
+            }
+        }
+
+        if !finished {
+            report.unfinished += 1;
+        }
+        report.games += 1;
+    }
+
+    report
+}
@@ -0,0 +1,220 @@
+//! Hashes of game state that every machine agrees on.
+//!
+//! `std::hash` is no good for comparing state across machines: its hashers
+//! are randomly keyed or may change between Rust versions, and derived
+//! `Hash` impls feed it platform-sized integers. `StateHash` instead writes
+//! each value in a fixed byte encoding into 64-bit FNV-1a. That is plenty
+//! for catching a desync, but it is not a cryptographic hash, so it cannot
+//! stop a peer who forges state on purpose.
+
+use super::players::{
+    CharacterlessPlayer,
+    BoosterlessPlayer,
+    MovelessPlayer,
+};
+use super::rules::RuleSet;
+use super::two_player_game::Phase;
+use nzsc_core::{
+    characters::Character,
+    boosters::Booster,
+    moves::Move,
+    streaks::{ CharacterStreak, MoveStreak, },
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A 64-bit FNV-1a hasher.
+#[derive(Clone, Copy, Debug)]
+pub struct StateHasher {
+    state: u64,
+}
+
+/// A value with a canonical encoding.
+///
+/// Integers are written little-endian, strings and lists are prefixed with
+/// their length, and each `Option` or enum variant with a tag byte.
+/// Characters, boosters and moves are written as their names.
+pub trait StateHash {
+    fn write_state(&self, hasher: &mut StateHasher);
+
+    fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        self.write_state(&mut hasher);
+
+        hasher.finish()
+    }
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= u64::from(byte);
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.write_bytes(&[value]);
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub fn write_str(&mut self, value: &str) {
+        self.write_u32(value.len() as u32);
+        self.write_bytes(value.as_bytes());
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateHash for u8 {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        hasher.write_u8(*self);
+    }
+}
+
+impl<T: StateHash> StateHash for Option<T> {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        match *self {
+            None => hasher.write_u8(0),
+            Some(ref value) => {
+                hasher.write_u8(1);
+                value.write_state(hasher);
+            },
+        }
+    }
+}
+
+impl<T: StateHash> StateHash for Vec<T> {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        hasher.write_u32(self.len() as u32);
+
+        for value in self {
+            value.write_state(hasher);
+        }
+    }
+}
+
+impl StateHash for Character {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        hasher.write_str(&self.to_string());
+    }
+}
+
+impl StateHash for Booster {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        hasher.write_str(&self.to_string());
+    }
+}
+
+impl StateHash for Move {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        hasher.write_str(&self.to_string());
+    }
+}
+
+impl StateHash for CharacterStreak {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        self.repeated_character.write_state(hasher);
+        self.times.write_state(hasher);
+    }
+}
+
+impl StateHash for MoveStreak {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        self.repeated_move.write_state(hasher);
+        self.times.write_state(hasher);
+    }
+}
+
+impl StateHash for CharacterlessPlayer {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        self.points.write_state(hasher);
+        self.waits.write_state(hasher);
+        self.character_streak.write_state(hasher);
+        self.banned_characters.write_state(hasher);
+        self.selected_character.write_state(hasher);
+    }
+}
+
+impl StateHash for BoosterlessPlayer {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        self.points.write_state(hasher);
+        self.waits.write_state(hasher);
+        self.character.write_state(hasher);
+        self.selected_booster.write_state(hasher);
+    }
+}
+
+impl StateHash for MovelessPlayer {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        self.points.write_state(hasher);
+        self.waits.write_state(hasher);
+        self.character.write_state(hasher);
+        self.booster.write_state(hasher);
+        self.move_streak.write_state(hasher);
+        self.destroyed_moves.write_state(hasher);
+        self.selected_move.write_state(hasher);
+    }
+}
+
+impl StateHash for Phase {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        match *self {
+            Phase::CharacterChoosing(ref a, ref b) => {
+                hasher.write_u8(0);
+                a.write_state(hasher);
+                b.write_state(hasher);
+            },
+            Phase::BoosterChoosing(ref a, ref b) => {
+                hasher.write_u8(1);
+                a.write_state(hasher);
+                b.write_state(hasher);
+            },
+            Phase::MoveChoosing(ref a, ref b) => {
+                hasher.write_u8(2);
+                a.write_state(hasher);
+                b.write_state(hasher);
+            },
+            Phase::GameOver(a_points, b_points) => {
+                hasher.write_u8(3);
+                a_points.write_state(hasher);
+                b_points.write_state(hasher);
+            },
+        }
+    }
+}
+
+impl StateHash for RuleSet {
+    fn write_state(&self, hasher: &mut StateHasher) {
+        self.points_to_win.write_state(hasher);
+        self.starting_waits.write_state(hasher);
+        self.streak_limit.write_state(hasher);
+        self.unparseable_penalty.write_state(hasher);
+        self.exhausted_character_penalty.write_state(hasher);
+        self.banned_character_penalty.write_state(hasher);
+        self.wrong_character_booster_penalty.write_state(hasher);
+        self.destroyed_move_penalty.write_state(hasher);
+        self.exhausted_move_penalty.write_state(hasher);
+        self.other_booster_move_penalty.write_state(hasher);
+        self.unavailable_move_penalty.write_state(hasher);
+        self.timeout_penalty.write_state(hasher);
+    }
+}
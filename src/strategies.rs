@@ -0,0 +1,193 @@
+//! Computer opponents.
+
+use super::choices::Choice;
+use super::random::Rng;
+use super::views::PlayerView;
+use super::two_player_game::{
+    Phase,
+    WhichPlayer,
+};
+use nzsc_core::{
+    characters::Character,
+    boosters::Booster,
+    moves::Move,
+    outcomes,
+};
+
+/// Picks choices for one player, seeing only that player's view of the game.
+pub trait Strategy {
+    /// Picks a choice for `view.player`, or `None` if there is nothing to choose.
+    fn choose(&mut self, view: &PlayerView) -> Option<Choice>;
+
+    /// Restarts any randomness from `seed`. Deterministic strategies ignore it.
+    fn reseed(&mut self, _seed: u64) {}
+}
+
+/// Picks uniformly at random from the legal choices.
+pub struct RandomStrategy {
+    rng: Rng,
+}
+
+/// Picks the choice that scores best against an opponent choosing uniformly at random.
+///
+/// Only the current phase is considered.
+pub struct GreedyStrategy;
+
+/// Like `GreedyStrategy`, but expects the opponent to keep repeating their
+/// current streak until the streak limit stops them.
+pub struct StreakAwareStrategy;
+
+impl RandomStrategy {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn choose(&mut self, view: &PlayerView) -> Option<Choice> {
+        let choices = view.legal_choices();
+
+        if choices.is_empty() {
+            None
+        } else {
+            Some(choices[self.rng.below(choices.len())])
+        }
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+}
+
+impl Strategy for GreedyStrategy {
+    fn choose(&mut self, view: &PlayerView) -> Option<Choice> {
+        best_response(view, false)
+    }
+}
+
+impl Strategy for StreakAwareStrategy {
+    fn choose(&mut self, view: &PlayerView) -> Option<Choice> {
+        best_response(view, true)
+    }
+}
+
+/// My points minus the opponent's for one pair of moves.
+pub(crate) fn move_score(booster: Booster, my_move: Move, opponent_booster: Booster, opponent_move: Move) -> i32 {
+    let points = outcomes::get_points(vec![(booster, my_move), (opponent_booster, opponent_move)]);
+
+    points[0] as i32 - points[1] as i32
+}
+
+/// My points minus the opponent's for one pair of characters.
+pub(crate) fn headstart_score(character: Character, opponent_character: Character) -> i32 {
+    let headstart = outcomes::get_headstart(character, opponent_character);
+
+    headstart.0 as i32 - headstart.1 as i32
+}
+
+/// How much more likely the opponent is to pick something they are on a streak with.
+///
+/// A streak of `times` makes the repeated pick `times + 1` times as likely as any other.
+fn streak_weight(is_repeated: bool, times: u8, follow_streaks: bool) -> f64 {
+    if follow_streaks && is_repeated {
+        1.0 + times as f64
+    } else {
+        1.0
+    }
+}
+
+fn expected<T: Copy>(options: &[(T, f64)], score: impl Fn(T) -> i32) -> f64 {
+    let total: f64 = options.iter().map(|&(_, weight)| weight).sum();
+    let sum: f64 = options.iter().map(|&(option, weight)| score(option) as f64 * weight).sum();
+
+    if total > 0.0 {
+        sum / total
+    } else {
+        0.0
+    }
+}
+
+fn best_response(view: &PlayerView, follow_streaks: bool) -> Option<Choice> {
+    let choices = view.legal_choices();
+    let is_a = view.player == WhichPlayer::PlayerA;
+
+    let value: Box<dyn Fn(Choice) -> f64> = match view.phase {
+        Phase::CharacterChoosing(ref a, ref b) => {
+            let opponent = if is_a { b } else { a };
+            let opponent_characters: Vec<(Character, f64)> = opponent.available_characters(&view.rules)
+                .into_iter()
+                .map(|character| {
+                    let streak = opponent.character_streak;
+                    (character, streak_weight(streak.repeated_character == Some(character), streak.times, follow_streaks))
+                })
+                .collect();
+
+            Box::new(move |choice| {
+                let character = choice.character().expect("character phase");
+                expected(&opponent_characters, |opponent_character| headstart_score(character, opponent_character))
+            })
+        },
+
+        Phase::BoosterChoosing(ref a, ref b) => {
+            let (me, opponent) = if is_a { (a, b) } else { (b, a) };
+            let character = me.character;
+            let mut opponent_moves: Vec<(Move, f64)> = opponent.character.get_moves()
+                .into_iter()
+                .map(|opponent_move| (opponent_move, 1.0))
+                .collect();
+            for booster in opponent.available_boosters() {
+                opponent_moves.extend(booster.get_moves().into_iter().map(|opponent_move| (opponent_move, 1.0)));
+            }
+
+            Box::new(move |choice| {
+                let booster = choice.booster().expect("booster phase");
+                let mut moves = character.get_moves();
+                moves.extend(booster.get_moves());
+
+                moves.into_iter()
+                    .map(|my_move| expected(&opponent_moves, |opponent_move| {
+                        move_score(booster, my_move, Booster::None, opponent_move)
+                    }))
+                    .fold(f64::MIN, f64::max)
+            })
+        },
+
+        Phase::MoveChoosing(ref a, ref b) => {
+            let (me, opponent) = if is_a { (a, b) } else { (b, a) };
+            let booster = me.booster;
+            let opponent_booster = opponent.booster;
+            let opponent_moves: Vec<(Move, f64)> = opponent.available_moves(&view.rules)
+                .into_iter()
+                .map(|opponent_move| {
+                    let streak = opponent.move_streak;
+                    (opponent_move, streak_weight(streak.repeated_move == Some(opponent_move), streak.times, follow_streaks))
+                })
+                .collect();
+
+            Box::new(move |choice| {
+                let my_move = choice.move_().expect("move phase");
+                expected(&opponent_moves, |opponent_move| move_score(booster, my_move, opponent_booster, opponent_move))
+            })
+        },
+
+        Phase::GameOver(_, _) => return None,
+    };
+
+    let mut best: Option<(Choice, f64)> = None;
+    for choice in choices {
+        let choice_value = value(choice);
+
+        let is_best = match best {
+            Some((_, best_value)) => choice_value > best_value,
+            None => true,
+        };
+
+        if is_best {
+            best = Some((choice, choice_value));
+        }
+    }
+
+    best.map(|(choice, _)| choice)
+}
@@ -0,0 +1,437 @@
+//! Two against two.
+//!
+//! Seats 0 and 1 are team A, and seats 2 and 3 team B. Everyone picks a
+//! character, booster and move of their own, and plays against both
+//! players on the other team but not against their teammate:
+//!
+//! - Characters are chosen again only when all four players chose the same
+//!   one. Otherwise each player earns their headstarts against both
+//!   opponents for their team.
+//! - Each player scores their move against both opponents' moves for their team.
+//! - A destructive move destroys the moves of both opponents, unless that
+//!   move was single-use and so used up anyway.
+//! - A player who runs out of waits gives the other team a point.
+//! - Points are pooled, so both teammates always have their team's score.
+//!   Teams win, reset for sudden death, and so on, the way players do in
+//!   the two-player game.
+//!
+//! How teammates' moves affect each other is set by `TeamRules`.
+//!
+//! Outcomes are reported with `MultiplayerEvent`s, where `player` is a seat
+//! and `GameOver` lists team A's score and then team B's.
+
+use super::choices::Choice;
+use super::multiplayer::{
+    MultiplayerEvent,
+    MultiplayerOutcome,
+};
+use super::players::{
+    CharacterlessPlayer,
+    BoosterlessPlayer,
+    MovelessPlayer,
+};
+use super::rules::RuleSet;
+use super::seats::{
+    Seat,
+    resolve_boosters,
+};
+use super::two_player_game::{
+    ChoiceError,
+    PenaltyReason,
+};
+use nzsc_core::{
+    characters::Character,
+    moves::{
+        Move,
+        SINGLE_USE_MOVES,
+        DESTRUCTIVE_MOVES,
+    },
+    outcomes,
+};
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize, };
+use std::convert::TryFrom;
+
+pub const SEATS: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Team {
+    A,
+    B,
+}
+
+/// How teammates' moves interact.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TeamRules {
+    /// A move destroyed or used up for one teammate is gone for both, if
+    /// the teammate has that move at all.
+    pub shared_destroyed_moves: bool,
+    /// Teammates share one move streak, with both their moves added to it
+    /// each round in seat order. A team then cannot lean on one move much
+    /// more than `RuleSet::streak_limit` times in a row between them.
+    ///
+    /// Both teammates can still pick the move the streak is on while it is
+    /// below the limit, which would take it past the limit. It stops at the
+    /// limit instead, so that move is exhausted for both from the next round.
+    pub shared_move_streaks: bool,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NZSCTeamGame {
+    pub phase: TeamPhase,
+    rules: RuleSet,
+    team_rules: TeamRules,
+}
+
+/// Like `Phase`, with the players in seat order.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TeamPhase {
+    CharacterChoosing([CharacterlessPlayer; SEATS]),
+    BoosterChoosing([BoosterlessPlayer; SEATS]),
+    MoveChoosing([MovelessPlayer; SEATS]),
+    /// Team A's score, then team B's.
+    GameOver(u8, u8),
+}
+
+impl Team {
+    pub fn of(seat: usize) -> Self {
+        if seat < 2 {
+            Team::A
+        } else {
+            Team::B
+        }
+    }
+
+    pub fn seats(self) -> [usize; 2] {
+        match self {
+            Team::A => [0, 1],
+            Team::B => [2, 3],
+        }
+    }
+
+    pub fn opponent(self) -> Self {
+        match self {
+            Team::A => Team::B,
+            Team::B => Team::A,
+        }
+    }
+}
+
+impl Default for TeamRules {
+    fn default() -> Self {
+        Self {
+            shared_destroyed_moves: true,
+            shared_move_streaks: true,
+        }
+    }
+}
+
+impl NZSCTeamGame {
+    pub fn new() -> Self {
+        Self::with_rules(RuleSet::default(), TeamRules::default())
+    }
+
+    pub fn with_rules(rules: RuleSet, team_rules: TeamRules) -> Self {
+        let player = CharacterlessPlayer::with_rules(&rules);
+
+        Self {
+            phase: TeamPhase::CharacterChoosing([player.clone(), player.clone(), player.clone(), player]),
+            rules,
+            team_rules,
+        }
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn team_rules(&self) -> &TeamRules {
+        &self.team_rules
+    }
+
+    /// Team A's score, then team B's.
+    pub fn scores(&self) -> (u8, u8) {
+        match self.phase {
+            TeamPhase::CharacterChoosing(ref players) => (players[0].points, players[2].points),
+            TeamPhase::BoosterChoosing(ref players) => (players[0].points, players[2].points),
+            TeamPhase::MoveChoosing(ref players) => (players[0].points, players[2].points),
+            TeamPhase::GameOver(a_points, b_points) => (a_points, b_points),
+        }
+    }
+
+    /// Which team won, or `None` if the game is still going.
+    pub fn winner(&self) -> Option<Team> {
+        match self.phase {
+            TeamPhase::GameOver(a_points, b_points) => Some(if a_points > b_points { Team::A } else { Team::B }),
+            _ => None,
+        }
+    }
+
+    /// Whether the player in `seat` still has to choose this phase.
+    pub fn is_waiting_on(&self, seat: usize) -> bool {
+        self.seat(seat).is_waiting()
+    }
+
+    fn seat(&self, seat: usize) -> Seat<'_> {
+        match self.phase {
+            TeamPhase::CharacterChoosing(ref players) => Seat::CharacterChoosing(&players[seat]),
+            TeamPhase::BoosterChoosing(ref players) => Seat::BoosterChoosing(&players[seat]),
+            TeamPhase::MoveChoosing(ref players) => Seat::MoveChoosing(&players[seat]),
+            TeamPhase::GameOver(_, _) => Seat::GameOver,
+        }
+    }
+
+    /// Parses `choice` according to the current phase and processes it, like
+    /// `NZSCTwoPlayerGame::process_choice`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `seat` is not below `SEATS`.
+    pub fn process_choice(&mut self, seat: usize, choice: String) -> Result<MultiplayerOutcome, ChoiceError> {
+        let parsed = self.seat(seat).parse_choice(&choice);
+        let reason = self.seat(seat).classify_choice(parsed, &self.rules)?;
+
+        Ok(self.resolve(seat, parsed, reason))
+    }
+
+    /// Processes a typed choice, like `NZSCTwoPlayerGame::choose`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `seat` is not below `SEATS`.
+    pub fn choose(&mut self, seat: usize, choice: Choice) -> Result<MultiplayerOutcome, ChoiceError> {
+        let reason = self.seat(seat).classify_choice(Some(choice), &self.rules)?;
+
+        Ok(self.resolve(seat, Some(choice), reason))
+    }
+
+    /// Applies a classified choice: the penalty for `reason` if there is one, otherwise `choice`.
+    fn resolve(&mut self, seat: usize, choice: Option<Choice>, reason: Option<PenaltyReason>) -> MultiplayerOutcome {
+        let rules = self.rules;
+        let team_rules = self.team_rules;
+        let mut events = vec![];
+        let mut penalty = None;
+        let mut earned = [0; SEATS];
+
+        if let Some(reason) = reason {
+            let point = match self.phase {
+                TeamPhase::CharacterChoosing(ref mut players) => players[seat].penalize(rules.penalty(reason)),
+                TeamPhase::BoosterChoosing(ref mut players) => players[seat].penalize(rules.penalty(reason)),
+                TeamPhase::MoveChoosing(ref mut players) => players[seat].penalize(rules.penalty(reason)),
+                TeamPhase::GameOver(_, _) => unreachable!(),
+            };
+
+            // Any point goes to the other team.
+            earned[Team::of(seat).opponent().seats()[0]] = point;
+            penalty = Some(reason.to_penalty(&rules, point > 0));
+        } else if let Some(choice) = choice {
+            let new_phase = match self.phase {
+                TeamPhase::CharacterChoosing(ref mut players) => {
+                    let character = choice.character().expect("classified as a character");
+                    events.push(MultiplayerEvent::CharacterSelected { player: seat, character });
+                    players[seat].selected_character = Some(character);
+
+                    resolve_characters(players, &mut earned, &mut events)
+                },
+                TeamPhase::BoosterChoosing(ref mut players) => {
+                    let booster = choice.booster().expect("classified as a booster");
+                    events.push(MultiplayerEvent::BoosterSelected { player: seat, booster });
+                    players[seat].selected_booster = Some(booster);
+
+                    resolve_boosters(players)
+                        .and_then(|next| <[MovelessPlayer; SEATS]>::try_from(next).ok())
+                        .map(TeamPhase::MoveChoosing)
+                },
+                TeamPhase::MoveChoosing(ref mut players) => {
+                    let selected_move = choice.move_().expect("classified as a move");
+                    events.push(MultiplayerEvent::MoveSelected { player: seat, selected_move });
+                    players[seat].selected_move = Some(selected_move);
+
+                    resolve_moves(players, &rules, team_rules, &mut earned, &mut events);
+                    None
+                },
+                TeamPhase::GameOver(_, _) => unreachable!(),
+            };
+
+            if let Some(new_phase) = new_phase {
+                self.phase = new_phase;
+            }
+        }
+
+        let (a_points, b_points) = self.scores();
+        self.settle_scores(
+            a_points + earned[0] + earned[1],
+            b_points + earned[2] + earned[3],
+            &mut events,
+        );
+
+        if let Some(penalty) = penalty {
+            events.push(MultiplayerEvent::PenaltyApplied { player: seat, penalty });
+        }
+        if let TeamPhase::GameOver(a_points, b_points) = self.phase {
+            events.push(MultiplayerEvent::GameOver { points: vec![a_points, b_points] });
+        }
+
+        MultiplayerOutcome {
+            penalty,
+            events,
+        }
+    }
+
+    /// Sets the team scores, then ends the game or starts sudden death once
+    /// a team has reached `points_to_win`.
+    fn settle_scores(&mut self, a_points: u8, b_points: u8, events: &mut Vec<MultiplayerEvent>) {
+        let rules = self.rules;
+
+        let (a_points, b_points) = if a_points >= rules.points_to_win || b_points >= rules.points_to_win {
+            if a_points != b_points {
+                self.phase = TeamPhase::GameOver(a_points, b_points);
+                return;
+            }
+
+            events.push(MultiplayerEvent::SuddenDeathReset { points: rules.sudden_death_points() });
+            (rules.sudden_death_points(), rules.sudden_death_points())
+        } else {
+            (a_points, b_points)
+        };
+
+        let scores = [a_points, a_points, b_points, b_points];
+        match self.phase {
+            TeamPhase::CharacterChoosing(ref mut players) => {
+                for (player, &score) in players.iter_mut().zip(&scores) {
+                    player.points = score;
+                }
+            },
+            TeamPhase::BoosterChoosing(ref mut players) => {
+                for (player, &score) in players.iter_mut().zip(&scores) {
+                    player.points = score;
+                }
+            },
+            TeamPhase::MoveChoosing(ref mut players) => {
+                for (player, &score) in players.iter_mut().zip(&scores) {
+                    player.points = score;
+                }
+            },
+            TeamPhase::GameOver(_, _) => {},
+        }
+    }
+}
+
+impl Default for NZSCTeamGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The seats of the players `seat` plays against.
+fn opponents(seat: usize) -> [usize; 2] {
+    Team::of(seat).opponent().seats()
+}
+
+fn teammate(seat: usize) -> usize {
+    seat ^ 1
+}
+
+/// Whether `a_move` belongs to `player`'s character or booster.
+fn has_move(player: &MovelessPlayer, a_move: Move) -> bool {
+    player.character.get_moves().contains(&a_move) || player.booster.get_moves().contains(&a_move)
+}
+
+/// Awards headstarts and moves on to boosters once everyone has a character.
+fn resolve_characters(players: &mut [CharacterlessPlayer; SEATS], earned: &mut [u8; SEATS], events: &mut Vec<MultiplayerEvent>) -> Option<TeamPhase> {
+    let characters: Vec<Character> = players.iter().map(|p| p.selected_character).collect::<Option<_>>()?;
+
+    if characters.iter().all(|&c| c == characters[0]) {
+        for player in players.iter_mut() {
+            player.selected_character = None;
+            player.character_streak.add(characters[0]);
+        }
+        events.push(MultiplayerEvent::CharactersTied(characters[0]));
+
+        return None;
+    }
+
+    for seat in 0..SEATS {
+        let points: u8 = opponents(seat).iter()
+            .map(|&opponent| outcomes::get_headstart(characters[seat], characters[opponent]).0)
+            .sum();
+
+        if points > 0 {
+            earned[seat] = points;
+            events.push(MultiplayerEvent::HeadstartAwarded { player: seat, points });
+        }
+    }
+
+    Some(TeamPhase::BoosterChoosing([
+        players[0].to_boosterless_player(characters[0]),
+        players[1].to_boosterless_player(characters[1]),
+        players[2].to_boosterless_player(characters[2]),
+        players[3].to_boosterless_player(characters[3]),
+    ]))
+}
+
+/// Scores the moves once everyone has one.
+fn resolve_moves(players: &mut [MovelessPlayer; SEATS], rules: &RuleSet, team_rules: TeamRules, earned: &mut [u8; SEATS], events: &mut Vec<MultiplayerEvent>) {
+    let moves: Vec<Move> = match players.iter().map(|p| p.selected_move).collect::<Option<_>>() {
+        Some(moves) => moves,
+        None => return,
+    };
+
+    for seat in 0..SEATS {
+        earned[seat] = opponents(seat).iter()
+            .map(|&opponent| outcomes::get_points(vec![
+                (players[seat].booster, moves[seat]),
+                (players[opponent].booster, moves[opponent]),
+            ])[0])
+            .sum();
+    }
+    events.push(MultiplayerEvent::MovesRevealed { moves: moves.clone(), points: earned.to_vec() });
+
+    for player in players.iter_mut() {
+        player.selected_move = None;
+    }
+
+    if team_rules.shared_move_streaks {
+        for &team in &[Team::A, Team::B] {
+            let [first, second] = team.seats();
+            let mut streak = players[first].move_streak;
+            streak.add(moves[first]);
+            streak.add(moves[second]);
+            streak.times = streak.times.min(rules.streak_limit);
+
+            players[first].move_streak = streak;
+            players[second].move_streak = streak;
+        }
+    } else {
+        for (player, &selected_move) in players.iter_mut().zip(&moves) {
+            player.move_streak.add(selected_move);
+        }
+    }
+
+    for seat in 0..SEATS {
+        let selected_move = moves[seat];
+        let is_destroyed = opponents(seat).iter().any(|&opponent| DESTRUCTIVE_MOVES.contains(&moves[opponent]));
+
+        if SINGLE_USE_MOVES.contains(&selected_move) {
+            events.push(MultiplayerEvent::SingleUseConsumed { player: seat, consumed_move: selected_move });
+        } else if is_destroyed {
+            events.push(MultiplayerEvent::MoveDestroyed { player: seat, destroyed_move: selected_move });
+        } else {
+            continue;
+        }
+
+        let mut losers = vec![seat];
+        if team_rules.shared_destroyed_moves && has_move(&players[teammate(seat)], selected_move) {
+            losers.push(teammate(seat));
+        }
+        for loser in losers {
+            if !players[loser].destroyed_moves.contains(&selected_move) {
+                players[loser].destroyed_moves.push(selected_move);
+            }
+        }
+    }
+}
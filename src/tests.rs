@@ -4,8 +4,69 @@ use super::two_player_game::{
     NZSCTwoPlayerGame,
     Phase,
     WhichPlayer,
+    ChoiceError,
+    Penalty,
+    PenaltyReason,
+    ChoiceOutcome,
 };
+use super::choices::Choice;
+use super::equilibrium::{
+    solve_matrix_game,
+    move_equilibrium,
+};
+use super::endings::GameEnd;
+use super::events::Event;
+use super::history::{
+    Action,
+    ReplayError,
+};
+use super::lobby::{
+    Lobby,
+    LobbyError,
+    Membership,
+};
+use super::mcts::MctsStrategy;
+use super::multiplayer::{
+    NZSCMultiplayerGame,
+    MultiplayerEvent,
+    MultiplayerPhase,
+};
+use super::rules::RuleSet;
+use super::timers::{
+    ManualClock,
+    TimedGame,
+    TimeoutPolicy,
+};
+use super::series::{
+    CarryOver,
+    Series,
+};
+use super::simulation::simulate;
+use super::state_hash::StateHash;
+use super::teams::{
+    NZSCTeamGame,
+    Team,
+    TeamPhase,
+    TeamRules,
+};
+use super::strategies::{
+    Strategy,
+    RandomStrategy,
+    GreedyStrategy,
+    StreakAwareStrategy,
+};
+use super::players::{
+    CharacterlessPlayer,
+    BoosterlessPlayer,
+    MovelessPlayer,
+};
+use super::characters::Character;
+use super::boosters::Booster;
 use super::moves::Move;
+use super::streaks::MoveStreak;
+use proptest::prelude::{ any, prop, };
+use std::time::{ Duration, Instant, };
+use proptest::strategy::Strategy as PropStrategy;
 
 #[test]
 fn it_works() {
@@ -154,5 +215,1996 @@ fn cant_pick_character_twice() {
     g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
     let e = g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string());
 
-    assert_eq!(e, Err(()));
+    assert_eq!(e, Err(ChoiceError::AlreadyChosen));
+}
+
+#[test]
+fn cant_choose_after_game_over() {
+    let mut g = NZSCTwoPlayerGame::new();
+    g.phase = Phase::GameOver(5, 2);
+
+    let e = g.process_choice(WhichPlayer::PlayerB, "Ninja".to_string());
+
+    assert_eq!(e, Err(ChoiceError::GameOver));
+}
+
+#[test]
+fn penalized_choices_are_reported() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    let outcome = g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    assert_eq!(outcome.penalty, None);
+
+    let outcome = g.process_choice(WhichPlayer::PlayerB, "Pirate".to_string()).unwrap();
+    assert_eq!(outcome.penalty, Some(Penalty {
+        reason: PenaltyReason::Unparseable,
+        waits: 4,
+        point_to_opponent: false,
+    }));
+
+    let outcome = g.process_choice(WhichPlayer::PlayerB, "Pirate".to_string()).unwrap();
+    assert_eq!(outcome.penalty, Some(Penalty {
+        reason: PenaltyReason::Unparseable,
+        waits: 4,
+        point_to_opponent: true,
+    }));
+
+    if let Phase::CharacterChoosing(ref a, ref b) = g.phase {
+        assert_eq!(a.points, 1);
+        assert_eq!(b.waits, 0);
+    } else {
+        panic!();
+    }
+}
+
+#[test]
+fn other_booster_moves_are_penalized() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+
+    g.process_choice(WhichPlayer::PlayerA, "Shadow".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Moustachio".to_string()).unwrap();
+
+    let outcome = g.process_choice(WhichPlayer::PlayerA, "Run In Circles".to_string()).unwrap();
+
+    if let Some(penalty) = outcome.penalty {
+        assert_eq!(penalty.reason, PenaltyReason::OtherBoosterMove);
+        assert_eq!(penalty.waits, 2);
+    } else {
+        panic!();
+    }
+}
+
+#[test]
+fn events_describe_choices() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    let outcome = g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    assert_eq!(outcome.events, vec![
+        Event::CharacterSelected {
+            player: WhichPlayer::PlayerA,
+            character: Character::Ninja,
+        },
+    ]);
+
+    let outcome = g.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+    assert_eq!(outcome.events, vec![
+        Event::CharacterSelected {
+            player: WhichPlayer::PlayerB,
+            character: Character::Clown,
+        },
+        Event::HeadstartAwarded {
+            player: WhichPlayer::PlayerB,
+            points: 1,
+        },
+    ]);
+
+    g.process_choice(WhichPlayer::PlayerA, "Speedy".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Moustachio".to_string()).unwrap();
+
+    g.process_choice(WhichPlayer::PlayerA, "Kick".to_string()).unwrap();
+    let outcome = g.process_choice(WhichPlayer::PlayerB, "Acid Spray".to_string()).unwrap();
+
+    assert_eq!(outcome.events, vec![
+        Event::MoveSelected {
+            player: WhichPlayer::PlayerB,
+            selected_move: Move::AcidSpray,
+        },
+        Event::MovesRevealed {
+            a_move: Move::Kick,
+            b_move: Move::AcidSpray,
+            a_points: 0,
+            b_points: 0,
+        },
+        Event::SingleUseConsumed {
+            player: WhichPlayer::PlayerB,
+            consumed_move: Move::AcidSpray,
+        },
+        Event::MoveDestroyed {
+            player: WhichPlayer::PlayerA,
+            destroyed_move: Move::Kick,
+        },
+    ]);
+}
+
+#[test]
+fn sudden_death_resets_points() {
+    let mut a = CharacterlessPlayer::new().to_boosterless_player(Character::Ninja).to_moveless_player(Booster::None);
+    let mut b = CharacterlessPlayer::new().to_boosterless_player(Character::Samurai).to_moveless_player(Booster::None);
+    a.points = 4;
+    b.points = 4;
+
+    let mut g = NZSCTwoPlayerGame::new();
+    g.phase = Phase::MoveChoosing(a, b);
+
+    g.process_choice(WhichPlayer::PlayerA, "Nunchucks".to_string()).unwrap();
+    let outcome = g.process_choice(WhichPlayer::PlayerB, "Samurai Sword".to_string()).unwrap();
+
+    assert_eq!(outcome.events.last(), Some(&Event::SuddenDeathReset { points: 4 }));
+
+    if let Phase::MoveChoosing(ref a, ref b) = g.phase {
+        assert_eq!(a.points, 4);
+        assert_eq!(b.points, 4);
+    } else {
+        panic!();
+    }
+}
+
+#[test]
+fn penalty_that_ends_game_emits_game_over() {
+    let mut g = NZSCTwoPlayerGame::new();
+    if let Phase::CharacterChoosing(ref mut a, ref mut b) = g.phase {
+        a.points = 4;
+        b.waits = 0;
+    }
+
+    let outcome = g.process_choice(WhichPlayer::PlayerB, "Pirate".to_string()).unwrap();
+
+    assert_eq!(outcome.events, vec![
+        Event::PenaltyApplied {
+            player: WhichPlayer::PlayerB,
+            penalty: Penalty {
+                reason: PenaltyReason::Unparseable,
+                waits: 4,
+                point_to_opponent: true,
+            },
+        },
+        Event::GameOver {
+            a_points: 5,
+            b_points: 0,
+        },
+    ]);
+}
+
+#[test]
+fn typed_choices_work() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    g.choose(WhichPlayer::PlayerA, Choice::Character(Character::Ninja)).unwrap();
+    g.choose(WhichPlayer::PlayerB, Choice::Character(Character::Clown)).unwrap();
+
+    let e = g.choose(WhichPlayer::PlayerA, Choice::Move(Move::Kick));
+    assert_eq!(e, Err(ChoiceError::WrongPhase));
+
+    if let Phase::BoosterChoosing(ref a, _) = g.phase {
+        assert_eq!(a.waits, 4);
+    } else {
+        panic!();
+    }
+
+    let outcome = g.choose(WhichPlayer::PlayerA, Choice::Booster(Booster::Shadow)).unwrap();
+    assert_eq!(outcome.penalty, None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn game_state_round_trips() {
+    extern crate serde_json;
+    extern crate bincode;
+
+    let mut g = NZSCTwoPlayerGame::new();
+
+    g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerA, "Shadow".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Moustachio".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerA, "Shadow Fireball".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Acid Spray".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerA, "Kick".to_string()).unwrap();
+
+    let json = serde_json::to_string(&g).unwrap();
+    let from_json: NZSCTwoPlayerGame = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&from_json).unwrap(), json);
+
+    let bytes = bincode::serialize(&g).unwrap();
+    let mut from_bytes: NZSCTwoPlayerGame = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(bincode::serialize(&from_bytes).unwrap(), bytes);
+
+    if let Phase::MoveChoosing(ref a, ref b) = from_bytes.phase {
+        assert_eq!(a.destroyed_moves, vec![Move::ShadowFireball]);
+        assert_eq!(b.destroyed_moves, vec![Move::AcidSpray]);
+        assert_eq!(a.selected_move, Some(Move::Kick));
+        assert_eq!(b.points, 1);
+    } else {
+        panic!();
+    }
+
+    let outcome = from_bytes.process_choice(WhichPlayer::PlayerB, "Nose".to_string()).unwrap();
+    assert_eq!(outcome.penalty, None);
+}
+
+#[test]
+fn replay_rebuilds_game() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerA, "Shadow".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Backwards".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Zombie".to_string()).unwrap();
+    g.choose(WhichPlayer::PlayerA, Choice::Move(Move::ShadowSlip)).unwrap();
+    g.process_choice(WhichPlayer::PlayerA, "Kick".to_string()).unwrap_err();
+    g.process_choice(WhichPlayer::PlayerB, "Nose".to_string()).unwrap();
+
+    assert_eq!(g.history().len(), 7);
+    assert_eq!(g.history()[4].action, Action::Choose("Zombie".to_string()));
+    assert_eq!(g.history()[5].action, Action::Choose("Shadow Slip".to_string()));
+
+    let replayed = NZSCTwoPlayerGame::replay(g.history()).unwrap();
+    assert_eq!(replayed.history(), g.history());
+
+    if let (Phase::MoveChoosing(a, b), Phase::MoveChoosing(c, d)) = (&g.phase, &replayed.phase) {
+        assert_eq!(a.points, c.points);
+        assert_eq!(b.points, d.points);
+        assert_eq!(b.waits, d.waits);
+    } else {
+        panic!();
+    }
+}
+
+#[test]
+fn replay_detects_tampering() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+
+    let mut history = g.history().to_vec();
+    history[1].action = Action::Choose("Samurai".to_string());
+    assert_eq!(NZSCTwoPlayerGame::replay(&history).err(), Some(ReplayError::Diverged { index: 1 }));
+
+    let mut history = g.history().to_vec();
+    history[1].player = WhichPlayer::PlayerA;
+    assert_eq!(NZSCTwoPlayerGame::replay(&history).err(), Some(ReplayError::Rejected {
+        index: 1,
+        error: ChoiceError::AlreadyChosen,
+    }));
+}
+
+#[test]
+fn undo_and_rewind() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    assert_eq!(g.undo(), Ok(None));
+
+    g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerA, "Shadow".to_string()).unwrap();
+
+    let undone = g.undo().unwrap().unwrap();
+    assert_eq!(undone.action, Action::Choose("Shadow".to_string()));
+    assert_eq!(g.history().len(), 2);
+
+    if let Phase::BoosterChoosing(ref a, ref b) = g.phase {
+        assert!(a.selected_booster.is_none());
+        assert_eq!(b.points, 1);
+    } else {
+        panic!();
+    }
+
+    assert!(g.rewind_to(2).unwrap().is_empty());
+
+    let undone = g.rewind_to(1).unwrap();
+    assert_eq!(undone.len(), 1);
+    assert_eq!(undone[0].action, Action::Choose("Clown".to_string()));
+
+    if let Phase::CharacterChoosing(ref a, ref b) = g.phase {
+        assert!(a.selected_character.is_some());
+        assert!(b.selected_character.is_none());
+    } else {
+        panic!();
+    }
+
+    g.process_choice(WhichPlayer::PlayerB, "Samurai".to_string()).unwrap();
+
+    if let Phase::BoosterChoosing(ref a, ref b) = g.phase {
+        assert_eq!(a.points, 1);
+        assert_eq!(b.points, 0);
+    } else {
+        panic!();
+    }
+}
+
+#[test]
+fn custom_rules_are_used() {
+    let rules = RuleSet {
+        points_to_win: 2,
+        starting_waits: 0,
+        unparseable_penalty: 1,
+        ..RuleSet::default()
+    };
+    let mut g = NZSCTwoPlayerGame::with_rules(rules);
+
+    let outcome = g.process_choice(WhichPlayer::PlayerA, "Pirate".to_string()).unwrap();
+    assert_eq!(outcome.penalty, Some(Penalty {
+        reason: PenaltyReason::Unparseable,
+        waits: 1,
+        point_to_opponent: true,
+    }));
+
+    g.process_choice(WhichPlayer::PlayerA, "Pirate".to_string()).unwrap();
+
+    if let Phase::GameOver(a_points, b_points) = g.phase {
+        assert_eq!(a_points, 0);
+        assert_eq!(b_points, 2);
+    } else {
+        panic!();
+    }
+
+    let replayed = NZSCTwoPlayerGame::replay_with_rules(rules, g.history()).unwrap();
+    assert_eq!(replayed.rules(), &rules);
+}
+
+#[test]
+fn streak_limit_is_configurable() {
+    let rules = RuleSet {
+        streak_limit: 1,
+        ..RuleSet::default()
+    };
+    let mut g = NZSCTwoPlayerGame::with_rules(rules);
+
+    g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Ninja".to_string()).unwrap();
+
+    let outcome = g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    assert_eq!(outcome.penalty.map(|penalty| penalty.reason), Some(PenaltyReason::ExhaustedCharacter));
+
+    if let Phase::CharacterChoosing(ref a, _) = g.phase {
+        assert_eq!(a.available_characters(&rules).len(), 3);
+        assert!(!a.available_characters(&rules).contains(&Character::Ninja));
+    } else {
+        panic!();
+    }
+}
+
+#[test]
+fn views_hide_opponent_choice() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+
+    let view = g.view_for(WhichPlayer::PlayerB);
+    assert!(view.opponent_has_chosen);
+    if let Phase::CharacterChoosing(ref a, ref b) = view.phase {
+        assert!(a.selected_character.is_none());
+        assert!(b.selected_character.is_none());
+    } else {
+        panic!();
+    }
+
+    let view = g.view_for(WhichPlayer::PlayerA);
+    assert!(!view.opponent_has_chosen);
+    if let Phase::CharacterChoosing(ref a, _) = view.phase {
+        assert!(a.selected_character == Some(Character::Ninja));
+    } else {
+        panic!();
+    }
+
+    if let Phase::CharacterChoosing(ref a, _) = g.phase {
+        assert!(a.selected_character == Some(Character::Ninja));
+    } else {
+        panic!();
+    }
+}
+
+#[test]
+fn legal_choices_are_never_penalized() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    for turn in 0..50 {
+        for &player in &[WhichPlayer::PlayerA, WhichPlayer::PlayerB] {
+            let choices = g.legal_choices(player);
+
+            for &choice in &choices {
+                let mut copy = g.clone();
+                assert_eq!(copy.choose(player, choice).unwrap().penalty, None);
+            }
+
+            if !choices.is_empty() {
+                g.choose(player, choices[turn % choices.len()]).unwrap();
+            }
+        }
+    }
+
+    g.phase = Phase::GameOver(5, 0);
+    assert!(g.legal_choices(WhichPlayer::PlayerA).is_empty());
+}
+
+#[test]
+fn no_legal_choices_after_choosing() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    assert_eq!(g.legal_choices(WhichPlayer::PlayerA).len(), 4);
+    g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    assert!(g.legal_choices(WhichPlayer::PlayerA).is_empty());
+    assert_eq!(g.legal_choices(WhichPlayer::PlayerB).len(), 4);
+}
+
+#[test]
+fn evaluate_choice_predicts_penalties() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    g.process_choice(WhichPlayer::PlayerA, "Zombie".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerA, "Regenerative".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Backwards".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerA, "Regenerate".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Nose".to_string()).unwrap();
+
+    assert_eq!(g.evaluate_choice(WhichPlayer::PlayerA, "Rampage"), Ok(None));
+    assert_eq!(g.evaluate_choice(WhichPlayer::PlayerA, "Regenerate"), Ok(Some(Penalty {
+        reason: PenaltyReason::DestroyedMove,
+        waits: 4,
+        point_to_opponent: false,
+    })));
+    assert_eq!(
+        g.evaluate_choice(WhichPlayer::PlayerA, "Apocalypse").unwrap().map(|penalty| penalty.reason),
+        Some(PenaltyReason::OtherBoosterMove)
+    );
+    assert_eq!(
+        g.evaluate_choice(WhichPlayer::PlayerA, "Kick").unwrap().map(|penalty| penalty.reason),
+        Some(PenaltyReason::UnavailableMove)
+    );
+    assert_eq!(
+        g.evaluate_choice(WhichPlayer::PlayerA, "Shadow").unwrap().map(|penalty| penalty.reason),
+        Some(PenaltyReason::Unparseable)
+    );
+
+    for choice in &["Rampage", "Regenerate", "Apocalypse", "Kick", "Shadow", "Zap"] {
+        let mut copy = g.clone();
+        let predicted = copy.evaluate_choice(WhichPlayer::PlayerA, choice).unwrap();
+        let outcome = copy.process_choice(WhichPlayer::PlayerA, choice.to_string()).unwrap();
+        assert_eq!(predicted, outcome.penalty);
+    }
+
+    g.process_choice(WhichPlayer::PlayerA, "Regenerate".to_string()).unwrap();
+
+    assert_eq!(g.evaluate_choice(WhichPlayer::PlayerA, "Regenerate"), Ok(Some(Penalty {
+        reason: PenaltyReason::DestroyedMove,
+        waits: 4,
+        point_to_opponent: true,
+    })));
+    assert_eq!(g.evaluate_choice(WhichPlayer::PlayerA, "Ninja"), Ok(Some(Penalty {
+        reason: PenaltyReason::Unparseable,
+        waits: 4,
+        point_to_opponent: true,
+    })));
+
+    g.phase = Phase::GameOver(5, 0);
+    assert_eq!(g.evaluate_choice(WhichPlayer::PlayerB, "Nose"), Err(ChoiceError::GameOver));
+}
+
+#[test]
+fn strategies_only_make_legal_choices() {
+    let mut strategies: Vec<Box<dyn Strategy>> = vec![
+        Box::new(RandomStrategy::new(7)),
+        Box::new(GreedyStrategy),
+        Box::new(StreakAwareStrategy),
+    ];
+
+    for strategy in &mut strategies {
+        let mut g = NZSCTwoPlayerGame::new();
+        let mut opponent = RandomStrategy::new(11);
+
+        for _ in 0..100 {
+            if let Some(choice) = strategy.choose(&g.view_for(WhichPlayer::PlayerA)) {
+                assert_eq!(g.choose(WhichPlayer::PlayerA, choice).unwrap().penalty, None);
+            }
+            if let Some(choice) = opponent.choose(&g.view_for(WhichPlayer::PlayerB)) {
+                assert_eq!(g.choose(WhichPlayer::PlayerB, choice).unwrap().penalty, None);
+            }
+        }
+    }
+}
+
+#[test]
+fn streak_aware_strategy_expects_repeats() {
+    let a = CharacterlessPlayer::new().to_boosterless_player(Character::Zombie).to_moveless_player(Booster::None);
+    let mut b = CharacterlessPlayer::new().to_boosterless_player(Character::Ninja).to_moveless_player(Booster::None);
+    b.move_streak.add(Move::Kick);
+    b.move_streak.add(Move::Kick);
+
+    let mut g = NZSCTwoPlayerGame::new();
+    g.phase = Phase::MoveChoosing(a, b);
+    let view = g.view_for(WhichPlayer::PlayerA);
+
+    assert_eq!(GreedyStrategy.choose(&view), Some(Choice::Move(Move::Rampage)));
+    assert_eq!(StreakAwareStrategy.choose(&view), Some(Choice::Move(Move::Muscle)));
+
+    g.phase = Phase::GameOver(5, 0);
+    assert_eq!(StreakAwareStrategy.choose(&g.view_for(WhichPlayer::PlayerA)), None);
+}
+
+#[test]
+fn solves_rock_paper_scissors() {
+    let solution = solve_matrix_game(&[
+        vec![0.0, -1.0, 1.0],
+        vec![1.0, 0.0, -1.0],
+        vec![-1.0, 1.0, 0.0],
+    ]).unwrap();
+
+    assert!(solution.value.abs() < 1e-6);
+    for &probability in solution.row_strategy.iter().chain(&solution.column_strategy) {
+        assert!((probability - 1.0 / 3.0).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn move_equilibrium_cannot_be_exploited() {
+    let a = CharacterlessPlayer::new().to_boosterless_player(Character::Samurai).to_moveless_player(Booster::Strong);
+    let b = CharacterlessPlayer::new().to_boosterless_player(Character::Ninja).to_moveless_player(Booster::Shadow);
+    let phase = Phase::MoveChoosing(a, b);
+    let rules = RuleSet::default();
+
+    assert!(move_equilibrium(&Phase::GameOver(5, 0), &rules).is_none());
+
+    let equilibrium = move_equilibrium(&phase, &rules).unwrap();
+    let matrix = super::equilibrium::PayoffMatrix::for_phase(&phase, &rules).unwrap();
+
+    let a_total: f64 = equilibrium.a_strategy.iter().map(|&(_, p)| p).sum();
+    let b_total: f64 = equilibrium.b_strategy.iter().map(|&(_, p)| p).sum();
+    assert!((a_total - 1.0).abs() < 1e-6);
+    assert!((b_total - 1.0).abs() < 1e-6);
+
+    for j in 0..matrix.b_moves.len() {
+        let payoff: f64 = (0..matrix.a_moves.len())
+            .map(|i| equilibrium.a_strategy[i].1 * matrix.payoffs[i][j] as f64)
+            .sum();
+        assert!(payoff >= equilibrium.value - 1e-6);
+    }
+    for i in 0..matrix.a_moves.len() {
+        let payoff: f64 = (0..matrix.b_moves.len())
+            .map(|j| equilibrium.b_strategy[j].1 * matrix.payoffs[i][j] as f64)
+            .sum();
+        assert!(payoff <= equilibrium.value + 1e-6);
+    }
+}
+
+/// Plays a game until A, a Clown without a booster, has no moves left: Acid
+/// Spray is used up, Nose is zapped, and Juggling Knives hits the streak limit.
+fn game_where_a_has_no_moves() -> NZSCTwoPlayerGame {
+    let rules = RuleSet {
+        points_to_win: 20,
+        ..RuleSet::default()
+    };
+    let mut g = NZSCTwoPlayerGame::with_rules(rules);
+
+    let choices = [
+        ("Clown", "Zombie"),
+        ("None", "None"),
+        ("Acid Spray", "Muscle"),
+        ("Nose", "Zap"),
+        ("Juggling Knives", "Rampage"),
+        ("Juggling Knives", "Rampage"),
+        ("Juggling Knives", "Rampage"),
+    ];
+    for &(a_choice, b_choice) in choices.iter() {
+        assert!(g.process_choice(WhichPlayer::PlayerA, a_choice.to_string()).unwrap().penalty.is_none());
+        assert!(g.process_choice(WhichPlayer::PlayerB, b_choice.to_string()).unwrap().penalty.is_none());
+    }
+
+    if let Phase::MoveChoosing(ref a, _) = g.phase {
+        assert!(a.available_moves(&rules).is_empty());
+    } else {
+        panic!();
+    }
+
+    g
+}
+
+#[test]
+fn move_equilibrium_needs_moves_on_both_sides() {
+    let g = game_where_a_has_no_moves();
+    assert!(move_equilibrium(&g.phase, g.rules()).is_none());
+
+    if let Phase::MoveChoosing(a, b) = g.phase.clone() {
+        let swapped = Phase::MoveChoosing(b, a);
+        assert!(move_equilibrium(&swapped, g.rules()).is_none());
+    } else {
+        panic!();
+    }
+
+    assert!(solve_matrix_game(&[]).is_none());
+    assert!(solve_matrix_game(&[vec![]]).is_none());
+}
+
+#[test]
+fn player_without_moves_is_still_waited_on() {
+    let mut g = game_where_a_has_no_moves();
+
+    assert!(g.is_waiting_on(WhichPlayer::PlayerA));
+    assert!(g.legal_choices(WhichPlayer::PlayerA).is_empty());
+    assert_eq!(NZSCTwoPlayerGame::new().cheapest_penalized_choice(WhichPlayer::PlayerA), None);
+
+    // Juggling Knives only hit the streak limit, which costs less than a used-up or destroyed move.
+    assert_eq!(g.cheapest_penalized_choice(WhichPlayer::PlayerA), Some(Choice::Move(Move::JugglingKnives)));
+
+    let penalty = g.process_choice(WhichPlayer::PlayerA, "Nose".to_string()).unwrap().penalty.unwrap();
+    assert_eq!(penalty.reason, PenaltyReason::DestroyedMove);
+    assert_eq!(penalty.to_string(), "Move has been destroyed, costing 4 waits");
+    assert!(g.is_waiting_on(WhichPlayer::PlayerA));
+}
+
+#[test]
+fn mcts_finds_winning_move() {
+    let mut a = CharacterlessPlayer::new().to_boosterless_player(Character::Zombie).to_moveless_player(Booster::None);
+    let mut b = CharacterlessPlayer::new().to_boosterless_player(Character::Ninja).to_moveless_player(Booster::None);
+    a.points = 4;
+    b.points = 4;
+    b.destroyed_moves = vec![Move::NinjaSword, Move::Nunchucks];
+
+    let mut g = NZSCTwoPlayerGame::new();
+    g.phase = Phase::MoveChoosing(a, b);
+
+    let mut mcts = MctsStrategy::new(3, 300);
+    assert_eq!(mcts.choose(&g.view_for(WhichPlayer::PlayerA)), Some(Choice::Move(Move::Muscle)));
+}
+
+#[test]
+fn mcts_is_deterministic() {
+    let mut g = NZSCTwoPlayerGame::new();
+    g.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+
+    let view = g.view_for(WhichPlayer::PlayerA);
+    let first = MctsStrategy::new(42, 200).choose(&view);
+    let second = MctsStrategy::new(42, 200).choose(&view);
+
+    assert!(first.is_some());
+    assert_eq!(first, second);
+    assert_eq!(MctsStrategy::new(42, 200).choose(&g.view_for(WhichPlayer::PlayerB)), None);
+}
+
+#[test]
+fn mcts_searches_past_a_forced_penalty() {
+    let mut g = game_where_a_has_no_moves();
+    if let Phase::MoveChoosing(_, ref mut b) = g.phase {
+        b.destroyed_moves.clear();
+        b.move_streak = MoveStreak::new();
+    }
+
+    let mut mcts = MctsStrategy::new(5, 200);
+    assert_eq!(mcts.choose(&g.view_for(WhichPlayer::PlayerA)), None);
+
+    let choice = mcts.choose(&g.view_for(WhichPlayer::PlayerB)).unwrap();
+    assert!(g.legal_choices(WhichPlayer::PlayerB).contains(&choice));
+}
+
+#[test]
+fn simulation_reports_every_game() {
+    let report = simulate(&mut RandomStrategy::new(1), &mut GreedyStrategy, 42, 20);
+
+    assert_eq!(report.games, 20);
+    assert_eq!(report.a.wins + report.b.wins + report.draws, 20);
+    assert_eq!(report.unfinished, 0);
+    assert!(report.average_game_length() >= 6.0);
+
+    let a_characters: u32 = report.a.character_picks.iter().map(|&(_, count)| count).sum();
+    let a_boosters: u32 = report.a.booster_picks.iter().map(|&(_, count)| count).sum();
+    assert!(a_characters >= 20);
+    assert_eq!(a_boosters, 20 - report.a.penalties.iter()
+        .filter(|&&(reason, _)| reason == PenaltyReason::WrongCharacterBooster)
+        .map(|&(_, count)| count)
+        .sum::<u32>());
+}
+
+#[test]
+fn simulation_is_deterministic() {
+    let first = simulate(&mut RandomStrategy::new(1), &mut RandomStrategy::new(2), 7, 10);
+    let second = simulate(&mut RandomStrategy::new(3), &mut RandomStrategy::new(4), 7, 10);
+
+    assert_eq!(first.a.wins, second.a.wins);
+    assert_eq!(first.b.wins, second.b.wins);
+    assert_eq!(first.total_choices, second.total_choices);
+}
+
+/// Describes each player's state, A first, as text so phases can be compared.
+fn describe_players(phase: &Phase) -> (String, String) {
+    fn option<T: ::std::fmt::Display>(value: Option<T>) -> String {
+        value.map_or("-".to_string(), |value| value.to_string())
+    }
+
+    match *phase {
+        Phase::CharacterChoosing(ref a, ref b) => {
+            let describe = |p: &CharacterlessPlayer| format!(
+                "{} {} {}x{} {}",
+                p.points, p.waits,
+                option(p.character_streak.repeated_character), p.character_streak.times,
+                option(p.selected_character),
+            );
+            (describe(a), describe(b))
+        },
+        Phase::BoosterChoosing(ref a, ref b) => {
+            let describe = |p: &BoosterlessPlayer| format!(
+                "{} {} {} {}",
+                p.points, p.waits, p.character, option(p.selected_booster),
+            );
+            (describe(a), describe(b))
+        },
+        Phase::MoveChoosing(ref a, ref b) => {
+            let describe = |p: &MovelessPlayer| format!(
+                "{} {} {} {} {}x{} {:?} {}",
+                p.points, p.waits, p.character, p.booster,
+                option(p.move_streak.repeated_move), p.move_streak.times,
+                p.destroyed_moves, option(p.selected_move),
+            );
+            (describe(a), describe(b))
+        },
+        Phase::GameOver(a, b) => (a.to_string(), b.to_string()),
+    }
+}
+
+/// Each player's points and, unless the game is over, waits.
+fn points_and_waits(phase: &Phase) -> ([u8; 2], Option<[u8; 2]>) {
+    match *phase {
+        Phase::CharacterChoosing(ref a, ref b) => ([a.points, b.points], Some([a.waits, b.waits])),
+        Phase::BoosterChoosing(ref a, ref b) => ([a.points, b.points], Some([a.waits, b.waits])),
+        Phase::MoveChoosing(ref a, ref b) => ([a.points, b.points], Some([a.waits, b.waits])),
+        Phase::GameOver(a, b) => ([a, b], None),
+    }
+}
+
+/// Choices that are illegal in some or all phases.
+const ARBITRARY_CHOICES: &[&str] = &[
+    "",
+    "Nonsense",
+    "Ninja",
+    "Zombie",
+    "Shadow",
+    "Strength",
+    "Kick",
+    "Zap",
+    "Shadow Fireball",
+    "Nunchucks",
+];
+
+/// Who chooses, whether they pick a legal choice, and which one.
+type Step = (bool, bool, usize);
+
+fn steps(max_len: usize) -> impl PropStrategy<Value = Vec<Step>> {
+    prop::collection::vec((any::<bool>(), prop::bool::weighted(0.8), any::<usize>()), 0..max_len)
+}
+
+/// Makes one step, returning the outcome if the choice was accepted.
+fn take_step(game: &mut NZSCTwoPlayerGame, (is_a, legal, index): Step) -> Option<ChoiceOutcome> {
+    let player = if is_a { WhichPlayer::PlayerA } else { WhichPlayer::PlayerB };
+    let choice = if legal {
+        let choices = game.legal_choices(player);
+        if choices.is_empty() {
+            return None;
+        }
+        choices[index % choices.len()].to_string()
+    } else {
+        ARBITRARY_CHOICES[index % ARBITRARY_CHOICES.len()].to_string()
+    };
+
+    game.process_choice(player, choice).ok()
+}
+
+proptest! {
+    #[test]
+    fn invariants_hold_over_random_play(steps in steps(400)) {
+        let mut game = NZSCTwoPlayerGame::new();
+        let starting_waits = game.rules().starting_waits;
+
+        for step in steps {
+            let (points_before, _) = points_and_waits(&game.phase);
+            let outcome = match take_step(&mut game, step) {
+                Some(outcome) => outcome,
+                None => continue,
+            };
+            let (points_after, waits) = points_and_waits(&game.phase);
+
+            if !outcome.events.iter().any(|event| matches!(*event, Event::SuddenDeathReset { .. })) {
+                prop_assert!(points_after[0] >= points_before[0]);
+                prop_assert!(points_after[1] >= points_before[1]);
+            }
+
+            if let Some(waits) = waits {
+                prop_assert!(waits[0] <= starting_waits);
+                prop_assert!(waits[1] <= starting_waits);
+            }
+
+            if let Phase::MoveChoosing(ref a, ref b) = game.phase {
+                for player in &[a, b] {
+                    for (i, destroyed) in player.destroyed_moves.iter().enumerate() {
+                        prop_assert!(!player.destroyed_moves[i + 1..].contains(destroyed));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn flipping_twice_changes_nothing(steps in steps(200)) {
+        let mut game = NZSCTwoPlayerGame::new();
+
+        for step in steps {
+            take_step(&mut game, step);
+
+            let (a, b) = describe_players(&game.phase);
+            let flipped = game.phase.flip_a_and_b();
+            prop_assert_eq!(describe_players(&flipped), (b, a));
+            prop_assert_eq!(describe_players(&flipped.flip_a_and_b()), describe_players(&game.phase));
+        }
+    }
+
+    #[test]
+    fn game_over_is_terminal(steps in steps(400), after in steps(20)) {
+        let mut game = NZSCTwoPlayerGame::new();
+        for step in steps {
+            take_step(&mut game, step);
+        }
+
+        if let Phase::GameOver(_, _) = game.phase {
+            let before = describe_players(&game.phase);
+            let history_len = game.history().len();
+
+            for (is_a, _, index) in after {
+                let player = if is_a { WhichPlayer::PlayerA } else { WhichPlayer::PlayerB };
+                let choice = ARBITRARY_CHOICES[index % ARBITRARY_CHOICES.len()].to_string();
+
+                prop_assert_eq!(game.process_choice(player, choice), Err(ChoiceError::GameOver));
+                prop_assert!(game.legal_choices(player).is_empty());
+            }
+
+            prop_assert_eq!(describe_players(&game.phase), before);
+            prop_assert_eq!(game.history().len(), history_len);
+        }
+    }
+
+    /// A player who only sends gibberish always loses within a fixed number of choices.
+    #[test]
+    fn unparseable_choices_end_the_game(a_choices in prop::collection::vec(any::<usize>(), 0..64)) {
+        let mut game = NZSCTwoPlayerGame::new();
+        let rules = *game.rules();
+        let free_penalties = (rules.starting_waits / rules.unparseable_penalty) as usize;
+        let limit = free_penalties + rules.points_to_win as usize;
+
+        for i in 0..limit {
+            take_step(&mut game, (true, true, a_choices.get(i).cloned().unwrap_or(0)));
+            take_step(&mut game, (false, false, 1));
+        }
+
+        match game.phase {
+            Phase::GameOver(a, b) => prop_assert!(a > b),
+            _ => prop_assert!(false, "game still going after {} unparseable choices", limit),
+        }
+    }
+
+    /// From any point reached by arbitrary play, once one player only makes
+    /// penalized choices while the other plays legally, the game ends within
+    /// the penalized player's waits divided by the smallest penalty, plus the
+    /// points needed to win.
+    #[test]
+    fn penalized_play_ends_the_game(prefix in steps(200), a_choices in prop::collection::vec(any::<usize>(), 0..64)) {
+        let mut game = NZSCTwoPlayerGame::new();
+        for step in prefix {
+            take_step(&mut game, step);
+        }
+
+        // If A has already chosen, B's choice ends the round first.
+        if game.evaluate_choice(WhichPlayer::PlayerA, "") == Err(ChoiceError::AlreadyChosen) {
+            prop_assume!(take_step(&mut game, (false, true, 0)).is_some());
+        }
+
+        let rules = *game.rules();
+        let smallest_penalty = [
+            rules.unparseable_penalty,
+            rules.exhausted_character_penalty,
+            rules.wrong_character_booster_penalty,
+            rules.destroyed_move_penalty,
+            rules.exhausted_move_penalty,
+            rules.other_booster_move_penalty,
+            rules.unavailable_move_penalty,
+        ].iter().cloned().min().unwrap();
+        let a_waits = match points_and_waits(&game.phase).1 {
+            Some(waits) => waits[0],
+            None => return Ok(()),
+        };
+        let limit = (a_waits / smallest_penalty + rules.points_to_win) as usize;
+
+        for i in 0..limit {
+            if let Phase::GameOver(_, _) = game.phase {
+                break;
+            }
+
+            take_step(&mut game, (false, true, 0));
+
+            let index = a_choices.get(i).cloned().unwrap_or(0);
+            let mut choice = ARBITRARY_CHOICES[index % ARBITRARY_CHOICES.len()];
+            if game.evaluate_choice(WhichPlayer::PlayerA, choice) == Ok(None) {
+                choice = "";
+            }
+            let outcome = game.process_choice(WhichPlayer::PlayerA, choice.to_string());
+            prop_assert!(outcome.unwrap().penalty.is_some());
+        }
+
+        match game.phase {
+            Phase::GameOver(a, b) => prop_assert!(b > a),
+            _ => prop_assert!(false, "game still going after {} penalized choices", limit),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn server_plays_a_match_over_localhost() {
+    extern crate serde_json;
+
+    use super::server::{ Server, ClientMessage, ServerMessage, };
+    use std::io::{ BufRead, BufReader, Write, };
+    use std::net::TcpStream;
+    use std::thread;
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Client {
+        fn connect(address: ::std::net::SocketAddr) -> Self {
+            let writer = TcpStream::connect(address).unwrap();
+            Self {
+                reader: BufReader::new(writer.try_clone().unwrap()),
+                writer,
+            }
+        }
+
+        fn send(&mut self, message: &ClientMessage) {
+            let mut line = serde_json::to_string(message).unwrap();
+            line.push('\n');
+            self.writer.write_all(line.as_bytes()).unwrap();
+        }
+
+        fn receive(&mut self) -> ServerMessage {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+            serde_json::from_str(&line).unwrap()
+        }
+
+        fn receive_view(&mut self) -> ::views::PlayerView {
+            match self.receive() {
+                ServerMessage::View { view } => view,
+                _ => panic!("Expected a view"),
+            }
+        }
+    }
+
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+
+    let mut a = Client::connect(address);
+    a.send(&ClientMessage::Create);
+    let code = match a.receive() {
+        ServerMessage::Joined { code, player } => {
+            assert_eq!(player, WhichPlayer::PlayerA);
+            code
+        },
+        _ => panic!("Expected a join"),
+    };
+    a.receive_view();
+
+    let mut b = Client::connect(address);
+    b.send(&ClientMessage::Join { code: code.clone() });
+    match b.receive() {
+        ServerMessage::Joined { player, .. } => assert_eq!(player, WhichPlayer::PlayerB),
+        _ => panic!("Expected a join"),
+    }
+    a.receive_view();
+    b.receive_view();
+
+    // B's pushed view hides A's pick.
+    a.send(&ClientMessage::Choose { choice: "Ninja".to_string() });
+    match a.receive() {
+        ServerMessage::Outcome { outcome } => assert_eq!(outcome.penalty, None),
+        _ => panic!("Expected an outcome"),
+    }
+    a.receive_view();
+    let view = b.receive_view();
+    assert!(view.opponent_has_chosen);
+
+    a.send(&ClientMessage::Choose { choice: "Zombie".to_string() });
+    match a.receive() {
+        ServerMessage::Error { message } => assert_eq!(message, ChoiceError::AlreadyChosen.to_string()),
+        _ => panic!("Expected an error"),
+    }
+    if let Phase::CharacterChoosing(ref a, _) = view.phase {
+        assert!(a.selected_character.is_none());
+    } else {
+        panic!("Not in character phase");
+    }
+
+    b.send(&ClientMessage::Choose { choice: "Clown".to_string() });
+    b.receive();
+    let view = a.receive_view();
+    b.receive_view();
+    if let Phase::BoosterChoosing(ref a, ref b) = view.phase {
+        assert_eq!((a.points, b.points), (0, 1));
+    } else {
+        panic!("Not in booster phase");
+    }
+
+    a.send(&ClientMessage::Resign);
+    assert_eq!(a.receive_view().end, Some(GameEnd::Resigned(WhichPlayer::PlayerA)));
+    b.receive_view();
+
+    let mut c = Client::connect(address);
+    c.send(&ClientMessage::Join { code: code.clone() });
+    match c.receive() {
+        ServerMessage::Error { message } => assert_eq!(message, LobbyError::RoomFull.to_string()),
+        _ => panic!("Expected an error"),
+    }
+
+    c.send(&ClientMessage::Spectate { code });
+    c.receive();
+    match c.receive() {
+        ServerMessage::SpectatorView { view } => match view.phase {
+            Phase::GameOver(_, _) => {},
+            _ => panic!("Game not over"),
+        },
+        _ => panic!("Expected a spectator view"),
+    }
+
+    a.send(&ClientMessage::Rematch);
+    for client in &mut [&mut a, &mut b, &mut c] {
+        match client.receive() {
+            ServerMessage::RematchRequested { player } => assert_eq!(player, WhichPlayer::PlayerA),
+            _ => panic!("Expected a rematch request"),
+        }
+    }
+    b.send(&ClientMessage::Rematch);
+    match a.receive_view().phase {
+        Phase::CharacterChoosing(_, _) => {},
+        _ => panic!("Rematch did not start"),
+    }
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn server_matches_queued_players() {
+    extern crate serde_json;
+
+    use super::server::{ Server, ClientMessage, ServerMessage, };
+    use std::io::{ BufRead, BufReader, Write, };
+    use std::net::TcpStream;
+    use std::thread;
+
+    fn send(stream: &mut TcpStream, message: &ClientMessage) {
+        let mut line = serde_json::to_string(message).unwrap();
+        line.push('\n');
+        stream.write_all(line.as_bytes()).unwrap();
+    }
+
+    fn receive(reader: &mut BufReader<TcpStream>) -> ServerMessage {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+
+    let mut a = TcpStream::connect(address).unwrap();
+    let mut a_reader = BufReader::new(a.try_clone().unwrap());
+    send(&mut a, &ClientMessage::Queue);
+    match receive(&mut a_reader) {
+        ServerMessage::Queued => {},
+        _ => panic!("Expected to be queued"),
+    }
+
+    let mut b = TcpStream::connect(address).unwrap();
+    let mut b_reader = BufReader::new(b.try_clone().unwrap());
+    send(&mut b, &ClientMessage::Queue);
+
+    let a_code = match receive(&mut a_reader) {
+        ServerMessage::Joined { code, player: WhichPlayer::PlayerA } => code,
+        _ => panic!("Expected to join as player A"),
+    };
+    let b_code = match receive(&mut b_reader) {
+        ServerMessage::Joined { code, player: WhichPlayer::PlayerB } => code,
+        _ => panic!("Expected to join as player B"),
+    };
+    assert_eq!(a_code, b_code);
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn server_disconnects_a_client_that_stops_reading() {
+    extern crate serde_json;
+
+    use super::server::{ Server, ClientMessage, ServerMessage, };
+    use std::io::{ BufRead, BufReader, ErrorKind, Write, };
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::Duration;
+
+    fn line(message: &ClientMessage) -> String {
+        let mut line = serde_json::to_string(message).unwrap();
+        line.push('\n');
+        line
+    }
+
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+
+    // Asks for its view over and over without ever reading the replies,
+    // until the server hangs up.
+    let mut stalled = TcpStream::connect(address).unwrap();
+    stalled.set_write_timeout(Some(Duration::from_secs(10))).unwrap();
+    stalled.write_all(line(&ClientMessage::Create).as_bytes()).unwrap();
+    let request = line(&ClientMessage::State).repeat(1000);
+    let error = (0..10_000)
+        .filter_map(|_| stalled.write_all(request.as_bytes()).err())
+        .next()
+        .expect("The stalled client was never disconnected");
+    assert!(
+        error.kind() != ErrorKind::WouldBlock && error.kind() != ErrorKind::TimedOut,
+        "The server stopped reading instead of disconnecting the stalled client",
+    );
+
+    let mut healthy = TcpStream::connect(address).unwrap();
+    healthy.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    let mut reader = BufReader::new(healthy.try_clone().unwrap());
+    let mut receive = || {
+        let mut reply = String::new();
+        reader.read_line(&mut reply).unwrap();
+        serde_json::from_str::<ServerMessage>(&reply).unwrap()
+    };
+
+    healthy.write_all(line(&ClientMessage::Create).as_bytes()).unwrap();
+    match receive() {
+        ServerMessage::Joined { .. } => {},
+        _ => panic!("Expected a join"),
+    }
+
+    healthy.write_all(line(&ClientMessage::State).as_bytes()).unwrap();
+    match receive() {
+        ServerMessage::View { .. } => {},
+        _ => panic!("Expected a view"),
+    }
+}
+
+#[test]
+fn lobby_rooms_join_by_code() {
+    let now = Instant::now();
+    let mut lobby = Lobby::new(1);
+
+    let code = lobby.create_room(1, now).unwrap();
+    assert_eq!(lobby.create_room(1, now), Err(LobbyError::AlreadyInLobby));
+    assert_eq!(lobby.join_room(2, "NOPE", now), Err(LobbyError::NoSuchRoom));
+    assert_eq!(lobby.join_room(2, &code, now), Ok(WhichPlayer::PlayerB));
+    assert_eq!(lobby.join_room(3, &code, now), Err(LobbyError::RoomFull));
+    lobby.spectate(3, &code, now).unwrap();
+    assert_eq!(lobby.room(&code).unwrap().members(), vec![1, 2, 3]);
+
+    lobby.choose(1, "Ninja".to_string(), now).unwrap();
+    assert_eq!(lobby.choose(3, "Ninja".to_string(), now), Err(LobbyError::NotAPlayer));
+    assert_eq!(lobby.choose(1, "Ninja".to_string(), now), Err(LobbyError::Choice(ChoiceError::AlreadyChosen)));
+
+    // Leaving mid-game forfeits it, and nobody can take the seat over.
+    lobby.leave(1);
+    let room = lobby.room(&code).unwrap();
+    assert_eq!(room.players, [None, Some(2)]);
+    assert_eq!(room.game.end(), Some(GameEnd::Forfeited(WhichPlayer::PlayerA)));
+    assert_eq!(lobby.join_room(4, &code, now), Err(LobbyError::GameStarted));
+
+    lobby.leave(2);
+    assert!(lobby.room(&code).is_none());
+    assert!(lobby.membership(3).is_none());
+}
+
+#[test]
+fn lobby_seat_stays_open_until_the_game_starts() {
+    let now = Instant::now();
+    let mut lobby = Lobby::new(1);
+
+    let code = lobby.create_room(1, now).unwrap();
+    lobby.spectate(2, &code, now).unwrap();
+    lobby.leave(1);
+    assert_eq!(lobby.join_room(3, &code, now), Err(LobbyError::NoSuchRoom));
+
+    let code = lobby.create_room(1, now).unwrap();
+    assert!(!lobby.room(&code).unwrap().has_started());
+    assert_eq!(lobby.choose(1, "Ninja".to_string(), now), Err(LobbyError::GameNotStarted));
+    assert_eq!(lobby.resign(1, now).map(|_| ()), Err(LobbyError::GameNotStarted));
+    assert_eq!(lobby.join_room(3, &code, now), Ok(WhichPlayer::PlayerB));
+    assert!(lobby.room(&code).unwrap().has_started());
+
+    // Leaving a finished game forfeits nothing.
+    lobby.resign(3, now).unwrap();
+    lobby.leave(1);
+    assert_eq!(lobby.room(&code).unwrap().game.end(), Some(GameEnd::Resigned(WhichPlayer::PlayerB)));
+    assert_eq!(lobby.join_room(4, &code, now), Err(LobbyError::GameStarted));
+    assert_eq!(lobby.request_rematch(3, now), Err(LobbyError::OpponentLeft));
+}
+
+#[test]
+fn lobby_queue_pairs_players() {
+    let now = Instant::now();
+    let mut lobby = Lobby::new(1);
+
+    assert_eq!(lobby.enqueue(1, now), Ok(None));
+    assert_eq!(lobby.membership(1), Some(&Membership::Queued));
+    lobby.leave(1);
+    assert_eq!(lobby.enqueue(2, now), Ok(None));
+
+    let code = lobby.enqueue(3, now).unwrap().unwrap();
+    assert_eq!(lobby.room(&code).unwrap().players, [Some(2), Some(3)]);
+    assert_eq!(lobby.membership(1), None);
+}
+
+#[test]
+fn lobby_rematch_after_game_over() {
+    let now = Instant::now();
+    let mut lobby = Lobby::new(1);
+    let code = lobby.create_room(1, now).unwrap();
+    lobby.join_room(2, &code, now).unwrap();
+
+    assert_eq!(lobby.request_rematch(1, now), Err(LobbyError::GameNotOver));
+    lobby.resign(2, now).unwrap();
+    assert_eq!(lobby.room(&code).unwrap().game.winner(), Some(WhichPlayer::PlayerA));
+
+    assert_eq!(lobby.request_rematch(1, now), Ok(false));
+    assert_eq!(lobby.request_rematch(2, now), Ok(true));
+    match lobby.room(&code).unwrap().game.phase {
+        Phase::CharacterChoosing(_, _) => {},
+        _ => panic!("Rematch did not start"),
+    }
+}
+
+#[test]
+fn lobby_closes_idle_rooms() {
+    let start = Instant::now();
+    let mut lobby = Lobby::new(1);
+    lobby.idle_timeout = Duration::from_secs(60);
+
+    let idle = lobby.create_room(1, start).unwrap();
+    let active = lobby.create_room(2, start).unwrap();
+    lobby.join_room(3, &active, start + Duration::from_secs(30)).unwrap();
+
+    let closed = lobby.remove_idle_rooms(start + Duration::from_secs(60));
+    assert_eq!(closed.len(), 1);
+    assert_eq!(closed[0].code, idle);
+    assert!(lobby.membership(1).is_none());
+    assert!(lobby.room(&active).is_some());
+}
+
+#[test]
+fn timeouts_penalize_through_waits() {
+    let clock = ManualClock::new();
+    let mut game = TimedGame::new(NZSCTwoPlayerGame::new(), clock.clone(), Duration::from_secs(30), TimeoutPolicy::Penalize);
+
+    game.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    clock.advance(Duration::from_secs(29));
+    assert!(game.check_timeouts().is_empty());
+    assert_eq!(game.remaining(WhichPlayer::PlayerA), None);
+    assert_eq!(game.remaining(WhichPlayer::PlayerB), Some(Duration::from_secs(1)));
+
+    clock.advance(Duration::from_secs(1));
+    let timed_out = game.check_timeouts();
+    assert_eq!(timed_out.len(), 1);
+    assert_eq!(timed_out[0].0, WhichPlayer::PlayerB);
+    assert_eq!(timed_out[0].1.penalty, Some(Penalty {
+        reason: PenaltyReason::TimedOut,
+        waits: 4,
+        point_to_opponent: false,
+    }));
+
+    // The timer restarts, and the next timeout costs a point.
+    assert_eq!(game.remaining(WhichPlayer::PlayerB), Some(Duration::from_secs(30)));
+    clock.advance(Duration::from_secs(30));
+    game.check_timeouts();
+    if let Phase::CharacterChoosing(ref a, ref b) = game.game().phase {
+        assert_eq!((a.points, b.waits), (1, 0));
+    } else {
+        panic!("Not in character phase");
+    }
+
+    // Timeouts are part of the history, so the game can be replayed.
+    let replayed = NZSCTwoPlayerGame::replay(game.game().history()).unwrap();
+    assert_eq!(replayed.history(), game.game().history());
+    assert_eq!(replayed.history()[1].action, Action::TimeOut);
+}
+
+#[test]
+fn timeouts_restart_each_round() {
+    let clock = ManualClock::new();
+    let mut game = TimedGame::new(NZSCTwoPlayerGame::new(), clock.clone(), Duration::from_secs(30), TimeoutPolicy::Penalize);
+
+    clock.advance(Duration::from_secs(20));
+    game.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    clock.advance(Duration::from_secs(5));
+    game.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+
+    assert_eq!(game.remaining(WhichPlayer::PlayerA), Some(Duration::from_secs(30)));
+    assert_eq!(game.remaining(WhichPlayer::PlayerB), Some(Duration::from_secs(30)));
+}
+
+#[test]
+fn timeouts_can_auto_pick_or_forfeit() {
+    let clock = ManualClock::new();
+    let mut game = TimedGame::new(NZSCTwoPlayerGame::new(), clock.clone(), Duration::from_secs(10), TimeoutPolicy::AutoPick);
+    clock.advance(Duration::from_secs(10));
+
+    let timed_out = game.check_timeouts();
+    assert_eq!(timed_out.len(), 2);
+    assert!(timed_out.iter().all(|timeout| timeout.1.penalty.is_none()));
+    assert_eq!(game.game().history().len(), 2);
+
+    let clock = ManualClock::new();
+    let mut game = TimedGame::new(NZSCTwoPlayerGame::new(), clock.clone(), Duration::from_secs(10), TimeoutPolicy::Forfeit);
+    game.process_choice(WhichPlayer::PlayerB, "Ninja".to_string()).unwrap();
+    clock.advance(Duration::from_secs(10));
+
+    let timed_out = game.check_timeouts();
+    assert_eq!(timed_out.len(), 1);
+    assert_eq!(timed_out[0].1.events, vec![
+        Event::Forfeited { player: WhichPlayer::PlayerA },
+        Event::GameOver { a_points: 0, b_points: 0 },
+    ]);
+    assert_eq!(game.game().winner(), Some(WhichPlayer::PlayerB));
+    assert!(game.check_timeouts().is_empty());
+}
+
+#[test]
+fn timers_stop_when_a_game_ends_early() {
+    let clock = ManualClock::new();
+    let mut game = TimedGame::new(NZSCTwoPlayerGame::new(), clock.clone(), Duration::from_secs(30), TimeoutPolicy::Penalize);
+
+    // A draw offer is not a choice, so it does not buy more time.
+    clock.advance(Duration::from_secs(20));
+    game.offer_draw(WhichPlayer::PlayerA).unwrap();
+    assert_eq!(game.remaining(WhichPlayer::PlayerA), Some(Duration::from_secs(10)));
+
+    game.accept_draw(WhichPlayer::PlayerB).unwrap();
+    assert_eq!(game.remaining(WhichPlayer::PlayerA), None);
+    assert_eq!(game.remaining(WhichPlayer::PlayerB), None);
+    assert_eq!(game.resign(WhichPlayer::PlayerA).map(|_| ()), Err(ChoiceError::GameOver));
+
+    let mut game = TimedGame::new(NZSCTwoPlayerGame::new(), clock.clone(), Duration::from_secs(30), TimeoutPolicy::Penalize);
+    game.resign(WhichPlayer::PlayerB).unwrap();
+    assert!(game.check_timeouts().is_empty());
+    clock.advance(Duration::from_secs(30));
+    assert!(game.check_timeouts().is_empty());
+    assert_eq!(game.game().winner(), Some(WhichPlayer::PlayerA));
+
+    let mut game = TimedGame::new(NZSCTwoPlayerGame::new(), clock.clone(), Duration::from_secs(30), TimeoutPolicy::Penalize);
+    game.forfeit(WhichPlayer::PlayerA).unwrap();
+    assert_eq!(game.remaining(WhichPlayer::PlayerB), None);
+}
+
+#[test]
+fn resigning_records_the_reason() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    g.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    g.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+    let outcome = g.resign(WhichPlayer::PlayerB).unwrap();
+
+    assert_eq!(outcome.events, vec![
+        Event::Resigned { player: WhichPlayer::PlayerB },
+        Event::GameOver { a_points: 0, b_points: 1 },
+    ]);
+    assert_eq!(g.end(), Some(GameEnd::Resigned(WhichPlayer::PlayerB)));
+    assert_eq!(g.winner(), Some(WhichPlayer::PlayerA));
+    assert_eq!(g.resign(WhichPlayer::PlayerA), Err(ChoiceError::GameOver));
+    assert_eq!(g.forfeit(WhichPlayer::PlayerA), Err(ChoiceError::GameOver));
+
+    let replayed = NZSCTwoPlayerGame::replay(g.history()).unwrap();
+    assert_eq!(replayed.end(), g.end());
+}
+
+#[test]
+fn winning_on_points_records_the_reason() {
+    let mut g = NZSCTwoPlayerGame::new();
+    assert_eq!(g.end(), None);
+    assert_eq!(g.winner(), None);
+
+    // Penalties alone end the game.
+    while g.end().is_none() {
+        g.process_choice(WhichPlayer::PlayerA, "Gibberish".to_string()).unwrap();
+    }
+
+    assert_eq!(g.end(), Some(GameEnd::Points));
+    assert_eq!(g.winner(), Some(WhichPlayer::PlayerB));
+}
+
+#[test]
+fn draws_must_be_offered_and_accepted() {
+    let mut g = NZSCTwoPlayerGame::new();
+
+    assert_eq!(g.accept_draw(WhichPlayer::PlayerB), Err(ChoiceError::NoDrawOffer));
+    g.offer_draw(WhichPlayer::PlayerA).unwrap();
+    assert_eq!(g.draw_offer(), Some(WhichPlayer::PlayerA));
+    assert_eq!(g.view_for(WhichPlayer::PlayerB).draw_offer, Some(WhichPlayer::PlayerA));
+    assert_eq!(g.accept_draw(WhichPlayer::PlayerA), Err(ChoiceError::NoDrawOffer));
+
+    // Choosing declines the offer.
+    g.process_choice(WhichPlayer::PlayerB, "Ninja".to_string()).unwrap();
+    assert_eq!(g.draw_offer(), None);
+    assert_eq!(g.accept_draw(WhichPlayer::PlayerB), Err(ChoiceError::NoDrawOffer));
+
+    g.offer_draw(WhichPlayer::PlayerB).unwrap();
+    let outcome = g.accept_draw(WhichPlayer::PlayerA).unwrap();
+    assert_eq!(outcome.events, vec![
+        Event::DrawAccepted { player: WhichPlayer::PlayerA },
+        Event::GameOver { a_points: 0, b_points: 0 },
+    ]);
+    assert_eq!(g.end(), Some(GameEnd::Drawn));
+    assert_eq!(g.winner(), None);
+    assert_eq!(g.offer_draw(WhichPlayer::PlayerA), Err(ChoiceError::GameOver));
+
+    let replayed = NZSCTwoPlayerGame::replay(g.history()).unwrap();
+    assert_eq!(replayed.end(), Some(GameEnd::Drawn));
+}
+
+#[cfg(feature = "commit-reveal")]
+#[test]
+fn commit_reveal_applies_choices_once_both_reveal() {
+    use super::commit_reveal::{ commit, CommitRevealError, CommitRevealGame, Reveal, };
+
+    let mut g = CommitRevealGame::new();
+    let a = Reveal::new("Ninja".to_string(), [1; 32]);
+    let b = Reveal::new("Clown".to_string(), [2; 32]);
+    assert_eq!(a.commitment(), commit("Ninja", [1; 32]));
+    assert!(a.commitment() != commit("Ninja", [3; 32]));
+
+    g.commit(WhichPlayer::PlayerA, a.commitment()).unwrap();
+    assert_eq!(g.commit(WhichPlayer::PlayerA, a.commitment()), Err(CommitRevealError::AlreadyCommitted));
+    assert_eq!(g.reveal(WhichPlayer::PlayerA, a.clone()), Err(CommitRevealError::WaitingForCommitments));
+    assert_eq!(g.reveal(WhichPlayer::PlayerB, b.clone()), Err(CommitRevealError::NotCommitted));
+    g.commit(WhichPlayer::PlayerB, b.commitment()).unwrap();
+
+    assert!(g.reveal(WhichPlayer::PlayerA, a.clone()).unwrap().is_empty());
+    assert_eq!(g.reveal(WhichPlayer::PlayerA, a), Err(CommitRevealError::AlreadyRevealed));
+    assert_eq!(g.game().history().len(), 0);
+
+    let outcomes = g.reveal(WhichPlayer::PlayerB, b).unwrap();
+    assert_eq!(outcomes.len(), 2);
+    assert_eq!(g.game().history().len(), 2);
+    if let Phase::BoosterChoosing(ref a, ref b) = g.game().phase {
+        assert_eq!((a.points, b.points), (0, 1));
+    } else {
+        panic!("Not in booster phase");
+    }
+
+    let replayed = CommitRevealGame::replay(RuleSet::default(), g.transcript()).unwrap();
+    assert_eq!(replayed.game().history(), g.game().history());
+}
+
+#[cfg(feature = "commit-reveal")]
+#[test]
+fn commit_reveal_drops_legal_choices_revealed_with_a_penalty() {
+    use super::commit_reveal::{ CommitRevealGame, Reveal, };
+
+    let mut g = CommitRevealGame::new();
+    let a = Reveal::new("Nonsense".to_string(), [1; 32]);
+    let b = Reveal::new("Clown".to_string(), [2; 32]);
+    g.commit(WhichPlayer::PlayerA, a.commitment()).unwrap();
+    g.commit(WhichPlayer::PlayerB, b.commitment()).unwrap();
+    g.reveal(WhichPlayer::PlayerA, a).unwrap();
+
+    // A is penalized, so B's choice is not applied, and A cannot pick the counter to it.
+    let outcomes = g.reveal(WhichPlayer::PlayerB, b).unwrap();
+    assert_eq!(outcomes.len(), 1);
+    assert_eq!(outcomes[0].0, WhichPlayer::PlayerA);
+    assert_eq!(outcomes[0].1.penalty.map(|penalty| penalty.reason), Some(PenaltyReason::Unparseable));
+    if let Phase::CharacterChoosing(ref a, ref b) = g.game().phase {
+        assert_eq!(a.waits, 0);
+        assert!(b.selected_character.is_none());
+    } else {
+        panic!("Not in character phase");
+    }
+    assert!(g.is_pending(WhichPlayer::PlayerA));
+    assert!(g.is_pending(WhichPlayer::PlayerB));
+    assert!(g.commitment(WhichPlayer::PlayerB).is_none());
+
+    // The next round is played from fresh commitments.
+    let a = Reveal::new("Ninja".to_string(), [3; 32]);
+    let b = Reveal::new("Zombie".to_string(), [4; 32]);
+    g.commit(WhichPlayer::PlayerA, a.commitment()).unwrap();
+    g.commit(WhichPlayer::PlayerB, b.commitment()).unwrap();
+    g.reveal(WhichPlayer::PlayerA, a).unwrap();
+    assert_eq!(g.reveal(WhichPlayer::PlayerB, b).unwrap().len(), 2);
+    if let Phase::BoosterChoosing(_, _) = g.game().phase {} else {
+        panic!("Not in booster phase");
+    }
+
+    let replayed = CommitRevealGame::replay(RuleSet::default(), g.transcript()).unwrap();
+    assert_eq!(replayed.game().history(), g.game().history());
+}
+
+#[cfg(feature = "commit-reveal")]
+#[test]
+fn commit_reveal_forfeits_mismatched_reveals() {
+    use super::commit_reveal::{ CommitRevealError, CommitRevealGame, InvalidReveal, Reveal, };
+
+    let mut g = CommitRevealGame::new();
+    let a = Reveal::new("Ninja".to_string(), [1; 32]);
+    let b = Reveal::new("Clown".to_string(), [2; 32]);
+    g.commit(WhichPlayer::PlayerA, a.commitment()).unwrap();
+    g.commit(WhichPlayer::PlayerB, b.commitment()).unwrap();
+    g.reveal(WhichPlayer::PlayerA, a).unwrap();
+
+    // B sees A's choice and tries to switch to the counter.
+    let outcomes = g.reveal(WhichPlayer::PlayerB, Reveal::new("Samurai".to_string(), [2; 32])).unwrap();
+    assert_eq!(outcomes[0].1.events[0], Event::Forfeited { player: WhichPlayer::PlayerB });
+    assert_eq!(g.game().end(), Some(GameEnd::Forfeited(WhichPlayer::PlayerB)));
+    assert_eq!(g.commit(WhichPlayer::PlayerA, b.commitment()), Err(CommitRevealError::Choice(ChoiceError::GameOver)));
+
+    let invalid = InvalidReveal::find_all(g.transcript());
+    assert_eq!(invalid.len(), 1);
+    assert_eq!(invalid[0].player, WhichPlayer::PlayerB);
+    assert_eq!((invalid[0].commit_index, invalid[0].reveal_index), (1, 3));
+    assert!(invalid[0].is_valid());
+
+    let replayed = CommitRevealGame::replay(RuleSet::default(), g.transcript()).unwrap();
+    assert_eq!(replayed.game().end(), g.game().end());
+}
+
+#[cfg(feature = "commit-reveal")]
+#[test]
+fn commit_reveal_times_out_missing_commitments_and_reveals() {
+    use super::commit_reveal::{ CommitRevealError, CommitRevealGame, Reveal, };
+
+    let mut g = CommitRevealGame::new();
+    let a = Reveal::new("Ninja".to_string(), [1; 32]);
+    g.commit(WhichPlayer::PlayerA, a.commitment()).unwrap();
+
+    // B never commits, so B is penalized and A's commitment stands.
+    let outcome = g.time_out(WhichPlayer::PlayerB).unwrap();
+    assert_eq!(outcome.penalty.map(|penalty| penalty.reason), Some(PenaltyReason::TimedOut));
+    assert!(g.commitment(WhichPlayer::PlayerA).is_some());
+    assert_eq!(g.time_out(WhichPlayer::PlayerA).map(|_| ()), Err(CommitRevealError::NotPending));
+
+    let b = Reveal::new("Clown".to_string(), [2; 32]);
+    g.commit(WhichPlayer::PlayerB, b.commitment()).unwrap();
+    g.reveal(WhichPlayer::PlayerA, a).unwrap();
+    assert_eq!(g.time_out(WhichPlayer::PlayerA).map(|_| ()), Err(CommitRevealError::NotPending));
+
+    // B has seen A's choice, so withholding the reveal forfeits.
+    let outcome = g.time_out(WhichPlayer::PlayerB).unwrap();
+    assert_eq!(outcome.events[0], Event::Forfeited { player: WhichPlayer::PlayerB });
+    assert_eq!(g.game().winner(), Some(WhichPlayer::PlayerA));
+
+    let replayed = CommitRevealGame::replay(RuleSet::default(), g.transcript()).unwrap();
+    assert_eq!(replayed.game().history(), g.game().history());
+}
+
+#[test]
+fn state_hashes_are_canonical() {
+    let g = NZSCTwoPlayerGame::new();
+    // Pinned, so a change to the encoding cannot slip by unnoticed.
+    assert_eq!(g.phase.state_hash(), 0xb731_3658_d3b3_942f);
+    assert_eq!(g.phase.state_hash(), g.clone().phase.state_hash());
+
+    let mut h = NZSCTwoPlayerGame::new();
+    h.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    assert!(h.phase.state_hash() != g.phase.state_hash());
+    assert!(h.phase.state_hash() != h.phase.flip_a_and_b().state_hash());
+
+    // Options and lists are tagged, so moving a value between fields changes the hash.
+    let mut a = CharacterlessPlayer::new();
+    a.points = 1;
+    let mut b = CharacterlessPlayer::new();
+    b.waits = 1;
+    b.points = 4;
+    assert!(Phase::CharacterChoosing(a.clone(), b.clone()).state_hash() != Phase::CharacterChoosing(b, a).state_hash());
+    assert!(RuleSet::default().state_hash() != RuleSet { points_to_win: 3, ..RuleSet::default() }.state_hash());
+}
+
+#[test]
+fn lockstep_peers_stay_in_sync_over_localhost() {
+    use super::lockstep::LockstepPeer;
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn play(mut peer: LockstepPeer, seed: u64) -> NZSCTwoPlayerGame {
+        let mut strategy = RandomStrategy::new(seed);
+
+        // Legal play need not end, so stop after a fixed number of rounds.
+        for _ in 0..200 {
+            if let Phase::GameOver(_, _) = peer.game().phase {
+                break;
+            }
+
+            let choice = strategy.choose(&peer.game().view_for(peer.player()))
+                .map_or("Nothing".to_string(), |choice| choice.to_string());
+            peer.play_round(Some(choice)).unwrap();
+        }
+
+        peer.game().clone()
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let host = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        play(LockstepPeer::new(stream, WhichPlayer::PlayerA, RuleSet::default()).unwrap(), 1)
+    });
+    let guest = play(LockstepPeer::connect(address, WhichPlayer::PlayerB, RuleSet::default()).unwrap(), 2);
+    let host = host.join().unwrap();
+
+    assert!(!guest.history().is_empty());
+    assert_eq!(host.history(), guest.history());
+    assert_eq!(host.phase.state_hash(), guest.phase.state_hash());
+}
+
+#[test]
+fn lockstep_detects_desync_and_mismatched_rules() {
+    use super::lockstep::{ LockstepError, LockstepPeer, };
+    use std::io::{ BufRead, BufReader, Write, };
+    use std::net::{ TcpListener, TcpStream, };
+    use std::thread;
+
+    fn fake_peer(lines: Vec<String>) -> (TcpStream, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let fake = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let reader = BufReader::new(stream.try_clone().unwrap());
+            for line in &lines {
+                writeln!(stream, "{}", line).unwrap();
+            }
+
+            reader.lines().map(Result::unwrap).collect()
+        });
+
+        (TcpStream::connect(address).unwrap(), fake)
+    }
+
+    let rules_hash = format!("{:016x}", RuleSet::default().state_hash());
+
+    let (stream, _) = fake_peer(vec![format!("hello A {}", rules_hash)]);
+    match LockstepPeer::new(stream, WhichPlayer::PlayerA, RuleSet::default()) {
+        Err(LockstepError::SameSeat) => {},
+        _ => panic!("Expected SameSeat"),
+    }
+
+    let other_rules = RuleSet { points_to_win: 3, ..RuleSet::default() };
+    let (stream, _) = fake_peer(vec![format!("hello B {:016x}", other_rules.state_hash())]);
+    match LockstepPeer::new(stream, WhichPlayer::PlayerA, RuleSet::default()) {
+        Err(LockstepError::RulesMismatch) => {},
+        _ => panic!("Expected RulesMismatch"),
+    }
+
+    // The fake peer claims B chose Ninja, but reports the state as if B chose Clown.
+    let mut expected = NZSCTwoPlayerGame::new();
+    expected.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    expected.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+    let (stream, fake) = fake_peer(vec![
+        format!("hello B {}", rules_hash),
+        "choose Ninja".to_string(),
+        format!("hash {:016x}", expected.phase.state_hash()),
+    ]);
+    let mut peer = LockstepPeer::new(stream, WhichPlayer::PlayerA, RuleSet::default()).unwrap();
+    match peer.play_round(Some("Ninja".to_string())) {
+        Err(LockstepError::Desync { remote, .. }) => assert_eq!(remote, expected.phase.state_hash()),
+        _ => panic!("Expected Desync"),
+    }
+    drop(peer);
+
+    let sent = fake.join().unwrap();
+    assert_eq!(sent[0], format!("hello A {}", rules_hash));
+    assert_eq!(sent[1], "choose Ninja");
+    assert!(sent[2].starts_with("hash "));
+}
+
+#[test]
+fn multiplayer_headstarts_count_every_opponent() {
+    let mut g = NZSCMultiplayerGame::new(3);
+    assert_eq!(g.player_count(), 3);
+
+    g.process_choice(0, "Ninja".to_string()).unwrap();
+    g.process_choice(1, "Ninja".to_string()).unwrap();
+    assert_eq!(g.process_choice(1, "Clown".to_string()), Err(ChoiceError::AlreadyChosen));
+    let outcome = g.process_choice(2, "Clown".to_string()).unwrap();
+
+    // Clown beats both Ninjas.
+    assert_eq!(outcome.events[1], MultiplayerEvent::HeadstartAwarded { player: 2, points: 2 });
+    assert_eq!(g.scores(), vec![0, 0, 2]);
+    if let MultiplayerPhase::BoosterChoosing(_) = g.phase {} else {
+        panic!("Not in booster phase");
+    }
+}
+
+#[test]
+fn multiplayer_characters_tie_only_when_everyone_matches() {
+    let mut g = NZSCMultiplayerGame::new(4);
+
+    for player in 0..4 {
+        g.process_choice(player, "Samurai".to_string()).unwrap();
+    }
+
+    if let MultiplayerPhase::CharacterChoosing(ref players) = g.phase {
+        assert!(players.iter().all(|p| p.selected_character.is_none() && p.character_streak.times == 1));
+    } else {
+        panic!("Not in character phase");
+    }
+}
+
+#[test]
+fn multiplayer_destructive_moves_hit_everyone_else() {
+    let mut g = NZSCMultiplayerGame::new(3);
+
+    for (player, character) in ["Zombie", "Ninja", "Ninja"].iter().enumerate() {
+        g.process_choice(player, character.to_string()).unwrap();
+    }
+    for player in 0..3 {
+        g.process_choice(player, "No Booster".to_string()).unwrap();
+    }
+    g.process_choice(0, "Zap".to_string()).unwrap();
+    g.process_choice(1, "Kick".to_string()).unwrap();
+    let outcome = g.process_choice(2, "Nunchucks".to_string()).unwrap();
+
+    assert!(outcome.events.contains(&MultiplayerEvent::SingleUseConsumed { player: 0, consumed_move: Move::Zap }));
+    assert!(outcome.events.contains(&MultiplayerEvent::MoveDestroyed { player: 1, destroyed_move: Move::Kick }));
+    assert!(outcome.events.contains(&MultiplayerEvent::MoveDestroyed { player: 2, destroyed_move: Move::Nunchucks }));
+}
+
+#[test]
+fn multiplayer_penalty_points_go_to_every_opponent() {
+    let mut g = NZSCMultiplayerGame::new(5);
+
+    // Four waits, so the second unparseable choice runs out.
+    g.process_choice(3, "Gibberish".to_string()).unwrap();
+    let outcome = g.process_choice(3, "Gibberish".to_string()).unwrap();
+
+    assert!(outcome.penalty.unwrap().point_to_opponent);
+    assert_eq!(g.scores(), vec![1, 1, 1, 0, 1]);
+    assert!(g.is_waiting_on(3));
+}
+
+#[test]
+fn multiplayer_shared_lead_goes_to_sudden_death() {
+    let rules = RuleSet { points_to_win: 1, ..RuleSet::default() };
+
+    // Ninja beats Samurai, Samurai beats Clown and Clown beats Ninja, so everyone reaches one point.
+    let mut g = NZSCMultiplayerGame::with_rules(3, rules);
+    g.process_choice(0, "Ninja".to_string()).unwrap();
+    g.process_choice(1, "Samurai".to_string()).unwrap();
+    let outcome = g.process_choice(2, "Clown".to_string()).unwrap();
+    assert!(outcome.events.contains(&MultiplayerEvent::SuddenDeathReset { points: 0 }));
+    assert_eq!(g.scores(), vec![0, 0, 0]);
+    assert_eq!(g.winner(), None);
+
+    // Only Clown scores against Ninja and Zombie.
+    let mut g = NZSCMultiplayerGame::with_rules(3, rules);
+    for (player, character) in ["Ninja", "Clown", "Zombie"].iter().enumerate() {
+        g.process_choice(player, character.to_string()).unwrap();
+    }
+    assert_eq!(g.winner(), Some(1));
+    assert_eq!(g.process_choice(0, "Shadow".to_string()), Err(ChoiceError::GameOver));
+}
+
+#[test]
+#[should_panic]
+fn multiplayer_needs_at_least_three_players() {
+    NZSCMultiplayerGame::new(2);
+}
+
+fn play_team_round(g: &mut NZSCTeamGame, choices: [&str; 4]) {
+    for (seat, choice) in choices.iter().enumerate() {
+        g.process_choice(seat, choice.to_string()).unwrap();
+    }
+}
+
+#[test]
+fn teams_pool_points_and_ignore_teammates() {
+    let mut g = NZSCTeamGame::new();
+    assert_eq!(Team::of(1), Team::A);
+    assert_eq!(Team::of(2), Team::B);
+
+    // Ninja beats both Samurai, and both Samurai beat Clown. Clown beating its teammate Ninja counts for nothing.
+    play_team_round(&mut g, ["Ninja", "Clown", "Samurai", "Samurai"]);
+
+    assert_eq!(g.scores(), (2, 2));
+    if let TeamPhase::BoosterChoosing(ref players) = g.phase {
+        assert!(players.iter().all(|p| p.points == 2));
+    } else {
+        panic!("Not in booster phase");
+    }
+
+    // Running out of waits gives the other team a point.
+    g.process_choice(3, "Gibberish".to_string()).unwrap();
+    let outcome = g.process_choice(3, "Gibberish".to_string()).unwrap();
+    assert!(outcome.penalty.unwrap().point_to_opponent);
+    assert_eq!(g.scores(), (3, 2));
+}
+
+#[test]
+fn teams_share_destroyed_moves_and_streaks() {
+    fn start(team_rules: TeamRules) -> NZSCTeamGame {
+        let rules = RuleSet { points_to_win: 20, ..RuleSet::default() };
+        let mut g = NZSCTeamGame::with_rules(rules, team_rules);
+        play_team_round(&mut g, ["Ninja", "Ninja", "Zombie", "Zombie"]);
+        play_team_round(&mut g, ["No Booster", "No Booster", "No Booster", "No Booster"]);
+
+        // Zap destroys both opponents' moves, but not its teammate's.
+        play_team_round(&mut g, ["Kick", "Kick", "Zap", "Rampage"]);
+        g
+    }
+
+    let shared = start(TeamRules::default());
+    if let TeamPhase::MoveChoosing(ref players) = shared.phase {
+        assert_eq!(players[0].destroyed_moves, vec![Move::Kick]);
+        assert_eq!(players[1].destroyed_moves, vec![Move::Kick]);
+        assert_eq!(players[3].destroyed_moves, vec![Move::Zap]);
+        assert_eq!(players[0].move_streak.times, 2);
+    } else {
+        panic!("Not in move phase");
+    }
+
+    let separate = start(TeamRules { shared_destroyed_moves: false, shared_move_streaks: false });
+    if let TeamPhase::MoveChoosing(ref players) = separate.phase {
+        assert!(players[3].destroyed_moves.is_empty());
+        assert_eq!(players[0].move_streak.times, 1);
+    } else {
+        panic!("Not in move phase");
+    }
+
+    // With a shared streak, both teammates can play Nunchucks in the round
+    // the streak reaches the limit, but it stops there.
+    let mut g = shared;
+    play_team_round(&mut g, ["Nunchucks", "Nunchucks", "Rampage", "Muscle"]);
+    play_team_round(&mut g, ["Nunchucks", "Nunchucks", "Muscle", "Rampage"]);
+    if let TeamPhase::MoveChoosing(ref players) = g.phase {
+        assert_eq!(players[0].move_streak.times, 3);
+        assert_eq!(players[1].move_streak.times, 3);
+    } else {
+        panic!("Not in move phase");
+    }
+    let outcome = g.process_choice(1, "Nunchucks".to_string()).unwrap();
+    assert_eq!(outcome.penalty.map(|penalty| penalty.reason), Some(PenaltyReason::ExhaustedMove));
+}
+
+#[test]
+fn teams_only_share_moves_a_teammate_has() {
+    let rules = RuleSet { points_to_win: 20, ..RuleSet::default() };
+    let mut g = NZSCTeamGame::with_rules(rules, TeamRules::default());
+    play_team_round(&mut g, ["Ninja", "Samurai", "Zombie", "Clown"]);
+    play_team_round(&mut g, ["No Booster", "No Booster", "No Booster", "No Booster"]);
+
+    // Zap destroys Kick and Helmet. The Samurai has no Kick and the Clown no Zap to lose.
+    play_team_round(&mut g, ["Kick", "Helmet", "Zap", "Nose"]);
+    if let TeamPhase::MoveChoosing(ref players) = g.phase {
+        assert_eq!(players[0].destroyed_moves, vec![Move::Kick]);
+        assert_eq!(players[1].destroyed_moves, vec![Move::Helmet]);
+        assert_eq!(players[2].destroyed_moves, vec![Move::Zap]);
+        assert!(players[3].destroyed_moves.is_empty());
+    } else {
+        panic!("Not in move phase");
+    }
+
+    let outcome = g.process_choice(1, "Kick".to_string()).unwrap();
+    assert_eq!(outcome.penalty.map(|penalty| penalty.reason), Some(PenaltyReason::UnavailableMove));
+}
+
+#[test]
+fn series_plays_until_a_majority() {
+    let mut series = Series::with_rules(3, RuleSet::default(), CarryOver::BanWinnersCharacter);
+    assert_eq!(series.games_to_win(), 2);
+
+    // B wins the first game with Clown.
+    series.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    series.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+    let outcome = series.resign(WhichPlayer::PlayerA).unwrap();
+    assert_eq!(*outcome.events.last().unwrap(), Event::GameOver { a_points: 0, b_points: 1 });
+    assert_eq!(series.wins(WhichPlayer::PlayerB), 1);
+    assert_eq!(series.winner(), None);
+
+    // The next game has started, and Clown is banned for B.
+    let view = series.view_for(WhichPlayer::PlayerB);
+    assert!(view.game.end.is_none());
+    assert_eq!((view.a_wins, view.b_wins, view.game_number, view.best_of), (0, 1, 2, 3));
+    assert!(series.game().banned_characters(WhichPlayer::PlayerB) == [Character::Clown]);
+    assert!(series.game().banned_characters(WhichPlayer::PlayerA).is_empty());
+    assert!(!series.legal_choices(WhichPlayer::PlayerB).contains(&Choice::Character(Character::Clown)));
+    assert!(series.legal_choices(WhichPlayer::PlayerA).contains(&Choice::Character(Character::Clown)));
+    let outcome = series.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+    assert_eq!(outcome.penalty.map(|penalty| penalty.reason), Some(PenaltyReason::BannedCharacter));
+
+    let game = series.game();
+    let banned = [vec![], vec![Character::Clown]];
+    let replayed = NZSCTwoPlayerGame::replay_with_bans(*game.rules(), banned, game.history()).unwrap();
+    assert_eq!(replayed.history(), game.history());
+    assert!(NZSCTwoPlayerGame::replay(game.history()).is_err());
+
+    // A draw counts for neither player, and A could still catch up in the last game.
+    series.offer_draw(WhichPlayer::PlayerA).unwrap();
+    series.accept_draw(WhichPlayer::PlayerB).unwrap();
+    assert_eq!((series.wins(WhichPlayer::PlayerA), series.wins(WhichPlayer::PlayerB)), (0, 1));
+    assert!(!series.is_over());
+
+    series.resign(WhichPlayer::PlayerA).unwrap();
+
+    assert_eq!(series.winner(), Some(WhichPlayer::PlayerB));
+    assert_eq!(series.finished_games().len(), 3);
+    assert_eq!(series.game().winner(), Some(WhichPlayer::PlayerB));
+    assert_eq!(series.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()), Err(ChoiceError::GameOver));
+}
+
+#[test]
+fn rewinding_a_series_game_keeps_its_ban() {
+    let mut series = Series::with_rules(3, RuleSet::default(), CarryOver::BanWinnersCharacter);
+    series.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+    series.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+    series.resign(WhichPlayer::PlayerA).unwrap();
+
+    let mut game = series.game().clone();
+    game.process_choice(WhichPlayer::PlayerB, "Ninja".to_string()).unwrap();
+    assert_eq!(game.rewind_to(0).unwrap().len(), 1);
+
+    let penalty = game.evaluate_choice(WhichPlayer::PlayerB, "Clown").unwrap();
+    assert_eq!(penalty.map(|penalty| penalty.reason), Some(PenaltyReason::BannedCharacter));
+}
+
+#[test]
+fn series_counts_draws_toward_best_of() {
+    let mut series = Series::new(3);
+
+    for _ in 0..3 {
+        assert!(!series.is_over());
+        series.offer_draw(WhichPlayer::PlayerA).unwrap();
+        series.accept_draw(WhichPlayer::PlayerB).unwrap();
+    }
+
+    assert!(series.is_over());
+    assert_eq!(series.winner(), None);
+    assert_eq!(series.finished_games().len(), 3);
+    assert_eq!(series.offer_draw(WhichPlayer::PlayerA).map(|_| ()), Err(ChoiceError::GameOver));
+}
+
+#[test]
+fn series_decided_on_points_with_nothing_carried_over() {
+    let rules = RuleSet { points_to_win: 1, ..RuleSet::default() };
+    let mut series = Series::with_rules(3, rules, CarryOver::Nothing);
+
+    for game in 0..2 {
+        // B can win with Clown again, since nothing carries over.
+        assert_eq!(series.evaluate_choice(WhichPlayer::PlayerB, "Clown"), Ok(None));
+        assert!(series.spectator_view().end.is_none());
+        assert_eq!(series.history().len(), 0);
+
+        series.process_choice(WhichPlayer::PlayerA, "Ninja".to_string()).unwrap();
+        assert!(series.spectator_view().a_has_chosen);
+        assert!(series.is_waiting_on(WhichPlayer::PlayerB));
+        assert_eq!(series.history().len(), 1);
+
+        // The Clown's headstart over the Ninja is the winning point.
+        series.process_choice(WhichPlayer::PlayerB, "Clown".to_string()).unwrap();
+        assert_eq!(series.wins(WhichPlayer::PlayerB), game + 1);
+    }
+
+    assert_eq!(series.winner(), Some(WhichPlayer::PlayerB));
+    assert!(series.finished_games().iter().all(|game| game.end() == Some(GameEnd::Points)));
+    assert_eq!(series.spectator_view().end, Some(GameEnd::Points));
+    assert_eq!(series.evaluate_choice(WhichPlayer::PlayerA, "Ninja"), Err(ChoiceError::GameOver));
 }
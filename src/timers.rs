@@ -0,0 +1,226 @@
+//! Turn timers, so a player who never chooses cannot stall a game.
+
+use super::choices::Choice;
+use super::random::Rng;
+use super::two_player_game::{
+    ChoiceError,
+    ChoiceOutcome,
+    NZSCTwoPlayerGame,
+    WhichPlayer,
+    seat_index,
+};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{ Duration, Instant, };
+
+/// A source of the current time.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The real time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+/// A clock that only moves when told to, for tests.
+///
+/// Clones share the same time, so one clone can be handed to a `TimedGame`
+/// and another kept to advance it.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    now: Rc<Cell<Instant>>,
+}
+
+/// What happens to a player whose time runs out.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TimeoutPolicy {
+    /// Charge them `RuleSet::timeout_penalty` waits and restart their timer.
+    Penalize,
+    /// Choose a random legal choice for them.
+    AutoPick,
+    /// End the game in the opponent's favor.
+    Forfeit,
+}
+
+/// A game where each player has `turn_limit` to make each choice.
+///
+/// A player's timer starts when they have something to choose, and
+/// restarts after each of their choices. Nothing happens on its own: call
+/// `check_timeouts` regularly to enforce the limit.
+pub struct TimedGame<C: Clock> {
+    game: NZSCTwoPlayerGame,
+    clock: C,
+    rng: Rng,
+    pub turn_limit: Duration,
+    pub policy: TimeoutPolicy,
+    /// When each player's timer started, A first, or `None` if they have nothing to choose.
+    started: [Option<Instant>; 2],
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            now: Rc::new(Cell::new(Instant::now())),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+impl<C: Clock> TimedGame<C> {
+    pub fn new(game: NZSCTwoPlayerGame, clock: C, turn_limit: Duration, policy: TimeoutPolicy) -> Self {
+        let mut timed = Self {
+            game,
+            clock,
+            rng: Rng::new(0),
+            turn_limit,
+            policy,
+            started: [None, None],
+        };
+        timed.restart_timers(None);
+
+        timed
+    }
+
+    pub fn game(&self) -> &NZSCTwoPlayerGame {
+        &self.game
+    }
+
+    pub fn into_game(self) -> NZSCTwoPlayerGame {
+        self.game
+    }
+
+    /// Restarts the randomness used by `TimeoutPolicy::AutoPick`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// How long `player` has left to choose, or `None` if they have nothing to choose.
+    pub fn remaining(&self, player: WhichPlayer) -> Option<Duration> {
+        let started = self.started[seat_index(player)]?;
+        let elapsed = self.clock.now().duration_since(started);
+
+        Some(self.turn_limit.checked_sub(elapsed).unwrap_or_default())
+    }
+
+    /// Like `NZSCTwoPlayerGame::process_choice`, restarting timers as needed.
+    pub fn process_choice(&mut self, chooser: WhichPlayer, choice: String) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.process_choice(chooser, choice)?;
+        self.restart_timers(Some(chooser));
+
+        Ok(outcome)
+    }
+
+    /// Like `NZSCTwoPlayerGame::choose`, restarting timers as needed.
+    pub fn choose(&mut self, chooser: WhichPlayer, choice: Choice) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.choose(chooser, choice)?;
+        self.restart_timers(Some(chooser));
+
+        Ok(outcome)
+    }
+
+    /// Like `NZSCTwoPlayerGame::resign`, stopping both timers.
+    pub fn resign(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.resign(player)?;
+        self.restart_timers(None);
+
+        Ok(outcome)
+    }
+
+    /// Like `NZSCTwoPlayerGame::forfeit`, stopping both timers.
+    pub fn forfeit(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.forfeit(player)?;
+        self.restart_timers(None);
+
+        Ok(outcome)
+    }
+
+    /// Like `NZSCTwoPlayerGame::offer_draw`. An offer is not a choice, so
+    /// the offering player's timer keeps running unless it ends the game.
+    pub fn offer_draw(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.offer_draw(player)?;
+        self.restart_timers(None);
+
+        Ok(outcome)
+    }
+
+    /// Like `NZSCTwoPlayerGame::accept_draw`, stopping both timers.
+    pub fn accept_draw(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.game.accept_draw(player)?;
+        self.restart_timers(None);
+
+        Ok(outcome)
+    }
+
+    /// Applies the timeout policy to every player whose time has run out.
+    pub fn check_timeouts(&mut self) -> Vec<(WhichPlayer, ChoiceOutcome)> {
+        let mut timed_out = vec![];
+
+        for &player in &[WhichPlayer::PlayerA, WhichPlayer::PlayerB] {
+            if self.remaining(player) != Some(Duration::from_secs(0)) {
+                continue;
+            }
+
+            let outcome = match self.policy {
+                TimeoutPolicy::Penalize => self.game.time_out(player),
+                TimeoutPolicy::AutoPick => {
+                    let choices = self.game.legal_choices(player);
+
+                    if choices.is_empty() {
+                        // Every choice left would be penalized anyway.
+                        self.game.time_out(player)
+                    } else {
+                        let choice = choices[self.rng.below(choices.len())];
+                        self.game.choose(player, choice)
+                    }
+                },
+                TimeoutPolicy::Forfeit => self.game.forfeit(player),
+            };
+
+            if let Ok(outcome) = outcome {
+                self.restart_timers(Some(player));
+                timed_out.push((player, outcome));
+            }
+        }
+
+        timed_out
+    }
+
+    /// Restarts the timer of `actor`, and of any player who did not have
+    /// something to choose before but does now.
+    fn restart_timers(&mut self, actor: Option<WhichPlayer>) {
+        let now = self.clock.now();
+
+        for &player in &[WhichPlayer::PlayerA, WhichPlayer::PlayerB] {
+            let index = seat_index(player);
+
+            self.started[index] = if !self.game.is_waiting_on(player) {
+                None
+            } else if actor == Some(player) || self.started[index].is_none() {
+                Some(now)
+            } else {
+                self.started[index]
+            };
+        }
+    }
+}
@@ -1,3 +1,12 @@
+use super::choices::Choice;
+use super::events::Event;
+use super::endings::GameEnd;
+use super::history::{
+    Action,
+    HistoryEntry,
+};
+use super::rules::RuleSet;
+use super::seats::Seat;
 use super::players::{
     CharacterlessPlayer,
     BoosterlessPlayer,
@@ -5,21 +14,33 @@ use super::players::{
 };
 use nzsc_core::{
     characters::Character,
-    boosters::Booster,
     moves::{
-        Move,
         SINGLE_USE_MOVES,
         DESTRUCTIVE_MOVES,
     },
     outcomes,
 };
-use std::str::FromStr;
+use std::fmt;
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize, };
+use std::error::Error;
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NZSCTwoPlayerGame {
     pub phase: Phase,
+    /// The phase before anything in `history`, which `rewind_to` replays from.
+    pub(crate) start: Phase,
+    rules: RuleSet,
+    pub(crate) history: Vec<HistoryEntry>,
+    /// How the game ended, once `phase` is `Phase::GameOver`.
+    pub(crate) end: Option<GameEnd>,
+    /// The player whose draw offer is waiting for an answer.
+    pub(crate) draw_offer: Option<WhichPlayer>,
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Phase {
     CharacterChoosing(CharacterlessPlayer, CharacterlessPlayer),
     BoosterChoosing(BoosterlessPlayer, BoosterlessPlayer),
@@ -27,255 +48,412 @@ pub enum Phase {
     GameOver(u8, u8),
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum WhichPlayer {
     PlayerA,
     PlayerB,
 }
 
+/// Why a choice was rejected outright.
+///
+/// A rejected choice leaves the game untouched.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ChoiceError {
+    /// The player has already locked in a choice this phase.
+    AlreadyChosen,
+    /// The game is over, so no more choices can be made.
+    GameOver,
+    /// The choice is for a different phase than the current one.
+    WrongPhase,
+    /// There is no draw offer from the opponent to accept.
+    NoDrawOffer,
+}
+
+/// Why an accepted choice cost the chooser waits.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PenaltyReason {
+    /// The choice was not a character, booster, or move name.
+    Unparseable,
+    /// The character has already been chosen three times in a row.
+    ExhaustedCharacter,
+    /// The chooser was banned from the character before the game started.
+    BannedCharacter,
+    /// The booster belongs to a different character.
+    WrongCharacterBooster,
+    /// The move has been destroyed.
+    DestroyedMove,
+    /// The move has already been chosen three times in a row.
+    ExhaustedMove,
+    /// The move belongs to one of the chooser's other boosters.
+    OtherBoosterMove,
+    /// The move belongs to a different character.
+    UnavailableMove,
+    /// The player's turn timer ran out before they chose.
+    TimedOut,
+}
+
+/// The cost of a penalized choice.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Penalty {
+    pub reason: PenaltyReason,
+    pub waits: u8,
+    /// Whether the chooser ran out of waits, giving the opponent a point.
+    pub point_to_opponent: bool,
+}
+
+/// What happened to a choice that was not rejected.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChoiceOutcome {
+    /// The penalty the choice incurred, if it was penalized.
+    pub penalty: Option<Penalty>,
+    pub events: Vec<Event>,
+}
+
 impl NZSCTwoPlayerGame {
     pub fn new() -> Self {
+        Self::with_rules(RuleSet::default())
+    }
+
+    pub fn with_rules(rules: RuleSet) -> Self {
+        Self::with_bans(rules, [vec![], vec![]])
+    }
+
+    /// A game where A, then B, may not pick the given characters.
+    /// See `PenaltyReason::BannedCharacter`.
+    pub fn with_bans(rules: RuleSet, banned_characters: [Vec<Character>; 2]) -> Self {
+        let [a_banned, b_banned] = banned_characters;
+        let mut a = CharacterlessPlayer::with_rules(&rules);
+        let mut b = CharacterlessPlayer::with_rules(&rules);
+        a.banned_characters = a_banned;
+        b.banned_characters = b_banned;
+
+        Self::from_phase(Phase::CharacterChoosing(a, b), rules)
+    }
+
+    /// A game that starts from `phase` with no history, for searching ahead from a view.
+    pub(crate) fn from_phase(phase: Phase, rules: RuleSet) -> Self {
+        let end = match phase {
+            Phase::GameOver(_, _) => Some(GameEnd::Points),
+            _ => None,
+        };
+
         Self {
-            phase: Phase::CharacterChoosing(
-                CharacterlessPlayer::new(),
-                CharacterlessPlayer::new()
-            ),
+            start: phase.clone(),
+            phase,
+            rules,
+            history: vec![],
+            end,
+            draw_offer: None,
+        }
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    /// Every accepted action so far, oldest first.
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+
+    /// The characters `player` was banned from when the game started.
+    pub fn banned_characters(&self, player: WhichPlayer) -> &[Character] {
+        match self.start {
+            Phase::CharacterChoosing(ref a, ref b) => &(if player == WhichPlayer::PlayerA { a } else { b }).banned_characters,
+            _ => &[],
+        }
+    }
+
+    /// Whether `player` still has to choose this phase.
+    ///
+    /// A player can be waited on even with no legal choices left, in which
+    /// case whatever they choose is penalized.
+    pub fn is_waiting_on(&self, player: WhichPlayer) -> bool {
+        self.seat(player).is_waiting()
+    }
+
+    fn seat(&self, player: WhichPlayer) -> Seat<'_> {
+        let is_a = player == WhichPlayer::PlayerA;
+
+        match self.phase {
+            Phase::CharacterChoosing(ref a, ref b) => Seat::CharacterChoosing(if is_a { a } else { b }),
+            Phase::BoosterChoosing(ref a, ref b) => Seat::BoosterChoosing(if is_a { a } else { b }),
+            Phase::MoveChoosing(ref a, ref b) => Seat::MoveChoosing(if is_a { a } else { b }),
+            Phase::GameOver(_, _) => Seat::GameOver,
+        }
+    }
+
+    /// Parses `choice` according to the current phase and processes it.
+    ///
+    /// A choice that cannot be parsed is penalized.
+    pub fn process_choice(&mut self, chooser: WhichPlayer, choice: String) -> Result<ChoiceOutcome, ChoiceError> {
+        let parsed = self.parse_choice(&choice[..]);
+        let outcome = self.resolve_choice(chooser, parsed)?;
+        self.record(chooser, Action::Choose(choice), &outcome.events);
+
+        Ok(outcome)
+    }
+
+    /// Processes a typed choice.
+    ///
+    /// Unlike `process_choice`, a choice for the wrong phase is rejected instead of penalized.
+    pub fn choose(&mut self, chooser: WhichPlayer, choice: Choice) -> Result<ChoiceOutcome, ChoiceError> {
+        let outcome = self.resolve_choice(chooser, Some(choice))?;
+        self.record(chooser, Action::Choose(choice.to_string()), &outcome.events);
+
+        Ok(outcome)
+    }
+
+    /// Processes choices that were made at the same time, A's first.
+    ///
+    /// Returns each choice's result in order. Choices left over once the
+    /// game ends are not processed.
+    pub(crate) fn process_simultaneous_choices<I>(&mut self, choices: I) -> Vec<(WhichPlayer, Result<ChoiceOutcome, ChoiceError>)>
+        where I: IntoIterator<Item = (WhichPlayer, String)>,
+    {
+        let mut results = vec![];
+
+        for (player, choice) in choices {
+            // A penalty to A can end the game before B's choice is applied.
+            if let Phase::GameOver(_, _) = self.phase {
+                break;
+            }
+
+            results.push((player, self.process_choice(player, choice)));
         }
+
+        results
+    }
+
+    /// Penalizes `player` for running out of time, as if they had made a `PenaltyReason::TimedOut` choice.
+    ///
+    /// Rejected if the player has already chosen this phase.
+    pub fn time_out(&mut self, player: WhichPlayer) -> Result<ChoiceOutcome, ChoiceError> {
+        // Checks that the player still has to choose.
+        self.classify_choice(player, None)?;
+
+        let outcome = self.resolve(player, None, Some(PenaltyReason::TimedOut));
+        self.record(player, Action::TimeOut, &outcome.events);
+
+        Ok(outcome)
+    }
+
+    pub(crate) fn record(&mut self, player: WhichPlayer, action: Action, events: &[Event]) {
+        self.history.push(HistoryEntry {
+            player,
+            action,
+            events: events.to_vec(),
+        });
+    }
+
+    /// Reports the penalty `process_choice` would apply to `choice`, without changing the game.
+    ///
+    /// `Ok(None)` means the choice is legal.
+    pub fn evaluate_choice(&self, chooser: WhichPlayer, choice: &str) -> Result<Option<Penalty>, ChoiceError> {
+        let reason = self.classify_choice(chooser, self.parse_choice(choice))?;
+
+        Ok(reason.map(|reason| {
+            let waits = match self.phase {
+                Phase::CharacterChoosing(ref a, ref b) => if chooser == WhichPlayer::PlayerA { a.waits } else { b.waits },
+                Phase::BoosterChoosing(ref a, ref b) => if chooser == WhichPlayer::PlayerA { a.waits } else { b.waits },
+                Phase::MoveChoosing(ref a, ref b) => if chooser == WhichPlayer::PlayerA { a.waits } else { b.waits },
+                Phase::GameOver(_, _) => unreachable!(),
+            };
+
+            reason.to_penalty(&self.rules, waits < self.rules.penalty(reason))
+        }))
+    }
+
+    fn parse_choice(&self, choice: &str) -> Option<Choice> {
+        // Every seat is in the same phase, so either one parses the same way.
+        self.seat(WhichPlayer::PlayerA).parse_choice(choice)
     }
 
-    pub fn process_choice(&mut self, chooser: WhichPlayer, choice: String) -> Result<(), ()> {
+    /// Decides whether `choice` is rejected, penalized, or legal.
+    ///
+    /// `None` stands for a choice that could not be parsed.
+    fn classify_choice(&self, chooser: WhichPlayer, choice: Option<Choice>) -> Result<Option<PenaltyReason>, ChoiceError> {
+        self.seat(chooser).classify_choice(choice, &self.rules)
+    }
+
+    fn resolve_choice(&mut self, chooser: WhichPlayer, choice: Option<Choice>) -> Result<ChoiceOutcome, ChoiceError> {
+        let reason = self.classify_choice(chooser, choice)?;
+
+        Ok(self.resolve(chooser, choice, reason))
+    }
+
+    /// Applies a classified choice: the penalty for `reason` if there is one, otherwise `choice`.
+    fn resolve(&mut self, chooser: WhichPlayer, choice: Option<Choice>, reason: Option<PenaltyReason>) -> ChoiceOutcome {
         let mut new_phase: Option<Phase> = None;
+        let mut penalty: Option<Penalty> = None;
 
-        let return_val = match &mut self.phase {
-            &mut Phase::CharacterChoosing(ref mut a, ref mut b) => {
-                let tuple = match chooser {
-                    WhichPlayer::PlayerA => {
-                        (a, b)
-                    },
-                    WhichPlayer::PlayerB => {
-                        (b, a)
-                    },
+        // Choosing instead of answering declines the opponent's draw offer.
+        if self.draw_offer == Some(chooser.opponent()) {
+            self.draw_offer = None;
+        }
+
+        let mut events: Vec<Event> = vec![];
+        let opponent = chooser.opponent();
+        let rules = self.rules;
+
+        match self.phase {
+            Phase::CharacterChoosing(ref mut a, ref mut b) => {
+                let (a, b) = match chooser {
+                    WhichPlayer::PlayerA => (a, b),
+                    WhichPlayer::PlayerB => (b, a),
                 };
-                let mut a = tuple.0;
-                let mut b = tuple.1;
-
-                if let Some(_) = a.selected_character {
-                    // Cannot repick.
-                    Err(())
-                } else {
-                    if let Ok(character) = Character::from_str(&choice[..]) {
-                        if a.character_streak.times == 3
-                            && a.character_streak.repeated_character == Some(character)
-                        {
-                            b.points += a.penalize(3);
-
-                            if b.points >= 5 {
-                                new_phase = Some(Phase::GameOver(a.points, b.points));
-                            }
 
-                            Ok(())
+                if let Some(reason) = reason {
+                    let point = a.penalize(rules.penalty(reason));
+                    b.points += point;
+                    penalty = Some(reason.to_penalty(&rules, point > 0));
+
+                    if b.points >= rules.points_to_win {
+                        new_phase = Some(Phase::GameOver(a.points, b.points));
+                    }
+                } else if let Some(character) = choice.and_then(Choice::character) {
+                    events.push(Event::CharacterSelected { player: chooser, character });
+
+                    if let Some(b_character) = b.selected_character {
+                        if character == b_character {
+                            a.selected_character = None;
+                            b.selected_character = None;
+                            a.character_streak.add(character);
+                            b.character_streak.add(character);
+                            events.push(Event::CharactersTied(character));
                         } else {
-                            if let Some(b_character) = b.selected_character {
-                                if character == b_character {
-                                    a.selected_character = None;
-                                    b.selected_character = None;
-                                    a.character_streak.add(character);
-                                    b.character_streak.add(character);
-                                    Ok(())
-                                } else {
-                                    let headstart = outcomes::get_headstart(character, b_character);
-
-                                    a.points += headstart.0;
-                                    b.points += headstart.1;
-
-                                    if a.points >= 5 || b.points >= 5 {
-                                        new_phase = Some(Phase::GameOver(a.points, b.points));
-                                    } else {
-                                        new_phase = Some(Phase::BoosterChoosing(
-                                            a.to_boosterless_player(character),
-                                            b.to_boosterless_player(b_character),
-                                        ));
-                                    }
-
-                                    Ok(())
-                                }
+                            let headstart = outcomes::get_headstart(character, b_character);
+
+                            a.points += headstart.0;
+                            b.points += headstart.1;
+
+                            if headstart.0 > 0 {
+                                events.push(Event::HeadstartAwarded { player: chooser, points: headstart.0 });
+                            }
+                            if headstart.1 > 0 {
+                                events.push(Event::HeadstartAwarded { player: opponent, points: headstart.1 });
+                            }
+
+                            if a.points >= rules.points_to_win || b.points >= rules.points_to_win {
+                                new_phase = Some(Phase::GameOver(a.points, b.points));
                             } else {
-                                a.selected_character = Some(character);
-                                Ok(())
+                                new_phase = Some(Phase::BoosterChoosing(
+                                    a.to_boosterless_player(character),
+                                    b.to_boosterless_player(b_character),
+                                ));
                             }
                         }
                     } else {
-                        b.points += a.penalize(4);
-
-                        if b.points >= 5 {
-                            new_phase = Some(Phase::GameOver(a.points, b.points));
-                        }
-
-                        Ok(())
+                        a.selected_character = Some(character);
                     }
                 }
             },
 
-            &mut Phase::BoosterChoosing(ref mut a, ref mut b) => {
-                let tuple = match chooser {
-                    WhichPlayer::PlayerA => {
-                        (a, b)
-                    },
-                    WhichPlayer::PlayerB => {
-                        (b, a)
-                    },
+            Phase::BoosterChoosing(ref mut a, ref mut b) => {
+                let (a, b) = match chooser {
+                    WhichPlayer::PlayerA => (a, b),
+                    WhichPlayer::PlayerB => (b, a),
                 };
-                let mut a = tuple.0;
-                let mut b = tuple.1;
-
-                if let Some(_) = a.selected_booster {
-                    // Cannot repick.
-                    Err(())
-                } else {
-                    if let Ok(booster) = Booster::from_str(&choice[..]) {
-                        if a.available_boosters().contains(&booster) {
-                            if let Some(b_booster) = b.selected_booster {
-                                new_phase = Some(Phase::MoveChoosing(
-                                    a.to_moveless_player(booster),
-                                    b.to_moveless_player(b_booster),
-                                ));
-
-                                Ok(())
-                            } else {
-                                a.selected_booster = Some(booster);
-                                Ok(())
-                            }
-                        } else {
-                            // Booster from wrong character.
-                            b.points += a.penalize(3);
 
-                            if b.points >= 5 {
-                                new_phase = Some(Phase::GameOver(a.points, b.points));
-                            }
+                if let Some(reason) = reason {
+                    let point = a.penalize(rules.penalty(reason));
+                    b.points += point;
+                    penalty = Some(reason.to_penalty(&rules, point > 0));
 
-                            Ok(())
-                        }
+                    if b.points >= rules.points_to_win {
+                        new_phase = Some(Phase::GameOver(a.points, b.points));
+                    }
+                } else if let Some(booster) = choice.and_then(Choice::booster) {
+                    events.push(Event::BoosterSelected { player: chooser, booster });
+
+                    if let Some(b_booster) = b.selected_booster {
+                        new_phase = Some(Phase::MoveChoosing(
+                            a.to_moveless_player(booster),
+                            b.to_moveless_player(b_booster),
+                        ));
                     } else {
-                        b.points += a.penalize(4);
-
-                        if b.points >= 5 {
-                            new_phase = Some(Phase::GameOver(a.points, b.points));
-                        }
-
-                        Ok(())
+                        a.selected_booster = Some(booster);
                     }
                 }
             },
 
-            &mut Phase::MoveChoosing(ref mut a, ref mut b) => {
-                let tuple = match chooser {
-                    WhichPlayer::PlayerA => {
-                        (a, b)
-                    },
-                    WhichPlayer::PlayerB => {
-                        (b, a)
-                    },
+            Phase::MoveChoosing(ref mut a, ref mut b) => {
+                let (a, b) = match chooser {
+                    WhichPlayer::PlayerA => (a, b),
+                    WhichPlayer::PlayerB => (b, a),
                 };
-                let mut a = tuple.0;
-                let mut b = tuple.1;
-
-                if let Some(_) = a.selected_move {
-                    // Cannot repick.
-                    Err(())
-                } else {
-                    if let Ok(a_move) = Move::from_str(&choice[..]) {
-                        if a.available_moves().contains(&a_move) {
-                            if let Some(b_move) = b.selected_move {
-                                let points = outcomes::get_points(vec![(a.booster, a_move), (b.booster, b_move)]);
-                                a.points += points[0];
-                                b.points += points[1];
-                                a.move_streak.add(a_move);
-                                b.move_streak.add(b_move);
-                                a.selected_move = None;
-                                b.selected_move = None;
-
-                                if SINGLE_USE_MOVES.contains(&a_move) || DESTRUCTIVE_MOVES.contains(&b_move) {
-                                    a.destroyed_moves.push(a_move);
-                                }
-                                if SINGLE_USE_MOVES.contains(&b_move) || DESTRUCTIVE_MOVES.contains(&a_move) {
-                                    b.destroyed_moves.push(b_move);
-                                }
-
-                                if a.points >= 5 || b.points >= 5 {
-                                    if a.points == b.points {
-                                        a.points = 4;
-                                        b.points = 4;
-                                    } else {
-                                        new_phase = Some(Phase::GameOver(a.points, b.points));
-                                    }
-                                }
-
-                                Ok(())
-                            } else {
-                                a.selected_move = Some(a_move);
-                                Ok(())
-                            }
-                        } else {
-                            if a.destroyed_moves.contains(&a_move) {
-                                b.points += a.penalize(4);
-
-                                if b.points >= 5 {
-                                    new_phase = Some(Phase::GameOver(a.points, b.points));
-                                }
 
-                                Ok(())
-                            } else if a.move_streak.times == 3
-                                && a.move_streak.repeated_move == Some(a_move)
-                            {
-                                b.points += a.penalize(3);
+                if let Some(reason) = reason {
+                    let point = a.penalize(rules.penalty(reason));
+                    b.points += point;
+                    penalty = Some(reason.to_penalty(&rules, point > 0));
 
-                                if b.points >= 5 {
-                                    new_phase = Some(Phase::GameOver(a.points, b.points));
-                                }
+                    if b.points >= rules.points_to_win {
+                        new_phase = Some(Phase::GameOver(a.points, b.points));
+                    }
+                } else if let Some(a_move) = choice.and_then(Choice::move_) {
+                    events.push(Event::MoveSelected { player: chooser, selected_move: a_move });
+
+                    if let Some(b_move) = b.selected_move {
+                        let points = outcomes::get_points(vec![(a.booster, a_move), (b.booster, b_move)]);
+                        a.points += points[0];
+                        b.points += points[1];
+                        a.move_streak.add(a_move);
+                        b.move_streak.add(b_move);
+                        a.selected_move = None;
+                        b.selected_move = None;
+
+                        events.push(match chooser {
+                            WhichPlayer::PlayerA => Event::MovesRevealed {
+                                a_move,
+                                b_move,
+                                a_points: points[0],
+                                b_points: points[1],
+                            },
+                            WhichPlayer::PlayerB => Event::MovesRevealed {
+                                a_move: b_move,
+                                b_move: a_move,
+                                a_points: points[1],
+                                b_points: points[0],
+                            },
+                        });
+
+                        if SINGLE_USE_MOVES.contains(&a_move) {
+                            a.destroyed_moves.push(a_move);
+                            events.push(Event::SingleUseConsumed { player: chooser, consumed_move: a_move });
+                        } else if DESTRUCTIVE_MOVES.contains(&b_move) {
+                            a.destroyed_moves.push(a_move);
+                            events.push(Event::MoveDestroyed { player: chooser, destroyed_move: a_move });
+                        }
+                        if SINGLE_USE_MOVES.contains(&b_move) {
+                            b.destroyed_moves.push(b_move);
+                            events.push(Event::SingleUseConsumed { player: opponent, consumed_move: b_move });
+                        } else if DESTRUCTIVE_MOVES.contains(&a_move) {
+                            b.destroyed_moves.push(b_move);
+                            events.push(Event::MoveDestroyed { player: opponent, destroyed_move: b_move });
+                        }
 
-                                Ok(())
+                        if a.points >= rules.points_to_win || b.points >= rules.points_to_win {
+                            if a.points == b.points {
+                                a.points = rules.sudden_death_points();
+                                b.points = rules.sudden_death_points();
+                                events.push(Event::SuddenDeathReset { points: rules.sudden_death_points() });
                             } else {
-                                let mut booster_moves = vec![];
-                                for booster in &a.character.get_boosters() {
-                                    booster_moves.extend(booster.get_moves());
-                                }
-
-                                if booster_moves.contains(&a_move) {
-                                    b.points += a.penalize(2);
-
-                                    if b.points >= 5 {
-                                        new_phase = Some(Phase::GameOver(a.points, b.points));
-                                    }
-
-                                    Ok(())
-                                } else {
-                                    b.points += a.penalize(3);
-
-                                    if b.points >= 5 {
-                                        new_phase = Some(Phase::GameOver(a.points, b.points));
-                                    }
-
-                                    Ok(())
-                                }
+                                new_phase = Some(Phase::GameOver(a.points, b.points));
                             }
                         }
                     } else {
-                        b.points += a.penalize(4);
-
-                        if b.points >= 5 {
-                            new_phase = Some(Phase::GameOver(a.points, b.points));
-                        }
-
-                        Ok(())
+                        a.selected_move = Some(a_move);
                     }
                 }
             },
 
-            &mut Phase::GameOver(_a_points, _b_points) => {
-                // You can't make a move after the game is over.
-                Err(())
-            },
-        };
+            Phase::GameOver(_a_points, _b_points) => unreachable!(),
+        }
 
         if let Some(new_phase) = new_phase {
             self.phase = if WhichPlayer::PlayerB == chooser {
@@ -285,25 +463,114 @@ impl NZSCTwoPlayerGame {
             };
         }
 
-        return_val
+        if let Some(penalty) = penalty {
+            events.push(Event::PenaltyApplied { player: chooser, penalty });
+        }
+
+        if let Phase::GameOver(a_points, b_points) = self.phase {
+            self.end = Some(GameEnd::Points);
+            self.draw_offer = None;
+            events.push(Event::GameOver { a_points, b_points });
+        }
+
+        ChoiceOutcome {
+            penalty,
+            events,
+        }
+    }
+}
+
+impl Default for NZSCTwoPlayerGame {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Phase {
     pub fn flip_a_and_b(&self) -> Self {
-        match self {
-            &Phase::CharacterChoosing(ref a, ref b) => {
+        match *self {
+            Phase::CharacterChoosing(ref a, ref b) => {
                 Phase::CharacterChoosing(b.clone(), a.clone())
             },
-            &Phase::BoosterChoosing(ref a, ref b) => {
+            Phase::BoosterChoosing(ref a, ref b) => {
                 Phase::BoosterChoosing(b.clone(), a.clone())
             },
-            &Phase::MoveChoosing(ref a, ref b) => {
+            Phase::MoveChoosing(ref a, ref b) => {
                 Phase::MoveChoosing(b.clone(), a.clone())
             },
-            &Phase::GameOver(ref a, ref b) => {
-                Phase::GameOver(b.clone(), a.clone())
+            Phase::GameOver(a, b) => {
+                Phase::GameOver(b, a)
             },
         }
     }
 }
+
+/// `player`'s index in per-player arrays, which list A first.
+pub(crate) fn seat_index(player: WhichPlayer) -> usize {
+    match player {
+        WhichPlayer::PlayerA => 0,
+        WhichPlayer::PlayerB => 1,
+    }
+}
+
+impl WhichPlayer {
+    pub fn opponent(self) -> Self {
+        match self {
+            WhichPlayer::PlayerA => WhichPlayer::PlayerB,
+            WhichPlayer::PlayerB => WhichPlayer::PlayerA,
+        }
+    }
+}
+
+impl PenaltyReason {
+    pub(crate) fn to_penalty(self, rules: &RuleSet, point_to_opponent: bool) -> Penalty {
+        Penalty {
+            reason: self,
+            waits: rules.penalty(self),
+            point_to_opponent,
+        }
+    }
+}
+
+impl fmt::Display for PenaltyReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let string = match *self {
+            PenaltyReason::Unparseable => "Choice was not recognized",
+            PenaltyReason::ExhaustedCharacter => "Character was chosen too many times in a row",
+            PenaltyReason::BannedCharacter => "Character is banned this game",
+            PenaltyReason::WrongCharacterBooster => "Booster belongs to a different character",
+            PenaltyReason::DestroyedMove => "Move has been destroyed",
+            PenaltyReason::ExhaustedMove => "Move was chosen too many times in a row",
+            PenaltyReason::OtherBoosterMove => "Move belongs to a different booster",
+            PenaltyReason::UnavailableMove => "Move belongs to a different character",
+            PenaltyReason::TimedOut => "Ran out of time",
+        };
+
+        write!(f, "{}", string)
+    }
+}
+
+impl fmt::Display for Penalty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.point_to_opponent {
+            write!(f, "{}, and out of waits, so the opponent gets a point", self.reason)
+        } else {
+            write!(f, "{}, costing {} waits", self.reason, self.waits)
+        }
+    }
+}
+
+impl fmt::Display for ChoiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let string = match *self {
+            ChoiceError::AlreadyChosen => "Player has already chosen this phase",
+            ChoiceError::GameOver => "Game is over",
+            ChoiceError::WrongPhase => "Choice does not belong to the current phase",
+            ChoiceError::NoDrawOffer => "Opponent has not offered a draw",
+        };
+
+        write!(f, "{}", string)
+    }
+}
+
+impl Error for ChoiceError {}
@@ -0,0 +1,169 @@
+use super::choices::Choice;
+use super::endings::GameEnd;
+use super::rules::RuleSet;
+use super::two_player_game::{
+    NZSCTwoPlayerGame,
+    Phase,
+    WhichPlayer,
+};
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize, };
+
+/// What one player is allowed to see of a game.
+///
+/// Safe to send to an untrusted client: the opponent's pending choice is
+/// removed from `phase`, leaving only `opponent_has_chosen`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlayerView {
+    /// The player this view is for.
+    pub player: WhichPlayer,
+    pub phase: Phase,
+    /// Whether the opponent has locked in a choice this phase.
+    pub opponent_has_chosen: bool,
+    /// The player whose draw offer is waiting for an answer.
+    pub draw_offer: Option<WhichPlayer>,
+    /// How the game ended, once it is over.
+    pub end: Option<GameEnd>,
+    pub rules: RuleSet,
+}
+
+/// What someone watching a game is allowed to see: neither player's pending choice.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpectatorView {
+    pub phase: Phase,
+    pub a_has_chosen: bool,
+    pub b_has_chosen: bool,
+    pub draw_offer: Option<WhichPlayer>,
+    pub end: Option<GameEnd>,
+    pub rules: RuleSet,
+}
+
+impl PlayerView {
+    /// Every choice the viewing player can make right now without being penalized.
+    ///
+    /// Empty if the player has already chosen this phase or the game is over.
+    pub fn legal_choices(&self) -> Vec<Choice> {
+        let a_is_player = self.player == WhichPlayer::PlayerA;
+
+        match self.phase {
+            Phase::CharacterChoosing(ref a, ref b) => {
+                let player = if a_is_player { a } else { b };
+
+                if player.selected_character.is_some() {
+                    vec![]
+                } else {
+                    player.available_characters(&self.rules).into_iter().map(Choice::Character).collect()
+                }
+            },
+            Phase::BoosterChoosing(ref a, ref b) => {
+                let player = if a_is_player { a } else { b };
+
+                if player.selected_booster.is_some() {
+                    vec![]
+                } else {
+                    player.available_boosters().into_iter().map(Choice::Booster).collect()
+                }
+            },
+            Phase::MoveChoosing(ref a, ref b) => {
+                let player = if a_is_player { a } else { b };
+
+                if player.selected_move.is_some() {
+                    vec![]
+                } else {
+                    player.available_moves(&self.rules).into_iter().map(Choice::Move).collect()
+                }
+            },
+            Phase::GameOver(_, _) => vec![],
+        }
+    }
+}
+
+impl NZSCTwoPlayerGame {
+    /// Every choice `player` can make right now without being penalized.
+    pub fn legal_choices(&self, player: WhichPlayer) -> Vec<Choice> {
+        self.view_for(player).legal_choices()
+    }
+
+    /// The choice that costs `player` the fewest waits, for when they must
+    /// choose but have no legal choices.
+    ///
+    /// `None` if `player` has a legal choice or is not being waited on.
+    pub fn cheapest_penalized_choice(&self, player: WhichPlayer) -> Option<Choice> {
+        if !self.is_waiting_on(player) || !self.legal_choices(player).is_empty() {
+            return None;
+        }
+
+        // Every character has boosters, and at most one character is exhausted
+        // and one banned, so only the move phase can leave a player without a
+        // legal choice.
+        let moves = match self.phase {
+            Phase::MoveChoosing(ref a, ref b) => {
+                let player = if player == WhichPlayer::PlayerA { a } else { b };
+                let mut moves = player.character.get_moves();
+                moves.extend(player.booster.get_moves());
+                moves
+            },
+            _ => return None,
+        };
+
+        moves.into_iter()
+            .min_by_key(|a_move| match self.evaluate_choice(player, &a_move.to_string()) {
+                Ok(penalty) => penalty.map_or(0, |penalty| penalty.waits),
+                Err(_) => u8::MAX,
+            })
+            .map(Choice::Move)
+    }
+
+    pub fn view_for(&self, player: WhichPlayer) -> PlayerView {
+        let mut phase = self.phase.clone();
+        let opponent_has_chosen = take_selection(&mut phase, player.opponent());
+
+        PlayerView {
+            player,
+            phase,
+            opponent_has_chosen,
+            draw_offer: self.draw_offer(),
+            end: self.end(),
+            rules: *self.rules(),
+        }
+    }
+
+    /// What someone watching the game is allowed to see.
+    pub fn spectator_view(&self) -> SpectatorView {
+        let mut phase = self.phase.clone();
+        let a_has_chosen = take_selection(&mut phase, WhichPlayer::PlayerA);
+        let b_has_chosen = take_selection(&mut phase, WhichPlayer::PlayerB);
+
+        SpectatorView {
+            phase,
+            a_has_chosen,
+            b_has_chosen,
+            draw_offer: self.draw_offer(),
+            end: self.end(),
+            rules: *self.rules(),
+        }
+    }
+}
+
+/// Removes `player`'s pending choice from `phase`, returning whether there was one.
+fn take_selection(phase: &mut Phase, player: WhichPlayer) -> bool {
+    let is_a = player == WhichPlayer::PlayerA;
+
+    match *phase {
+        Phase::CharacterChoosing(ref mut a, ref mut b) => {
+            let player = if is_a { a } else { b };
+            player.selected_character.take().is_some()
+        },
+        Phase::BoosterChoosing(ref mut a, ref mut b) => {
+            let player = if is_a { a } else { b };
+            player.selected_booster.take().is_some()
+        },
+        Phase::MoveChoosing(ref mut a, ref mut b) => {
+            let player = if is_a { a } else { b };
+            player.selected_move.take().is_some()
+        },
+        Phase::GameOver(_, _) => false,
+    }
+}